@@ -0,0 +1,314 @@
+/// 电平检测的时间常数（毫秒）
+const LEVEL_WINDOW_MS: f32 = 50.0;
+/// 限幅器释放时间（毫秒）
+const LIMITER_RELEASE_MS: f32 = 50.0;
+/// 电平和增益的更新块长（毫秒），块内对增益线性插值
+const BLOCK_MS: f32 = 10.0;
+/// 低于该电平视为静音，保持当前增益
+const GATE_DBFS: f32 = -45.0;
+/// 电平高出噪声底至少该值（dB）才允许提升增益，稳定的底噪不会被拉向目标电平
+const SPEECH_MARGIN_DB: f32 = 6.0;
+/// 放大后的噪声底不超过该电平：噪声底越高，允许的最大增益越小
+const NOISE_CEILING_DBFS: f32 = -40.0;
+/// 噪声底上升速度（dB/秒）；电平低于噪声底时立即跟随
+const NOISE_RISE_DB_PER_SECOND: f32 = 3.0;
+/// 数字静音时的电平下限（dBFS）
+const MIN_LEVEL_DBFS: f32 = -100.0;
+
+/// AGC 参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcParams {
+    /// 目标语音电平（dBFS，按 RMS 计算）
+    pub target_dbfs: f32,
+    /// 最大增益（dB），同时也是最大衰减量
+    pub max_gain_db: f32,
+    /// 增益下降（电平过高）的响应时间（毫秒）
+    pub attack_ms: f32,
+    /// 增益回升（电平过低）的响应时间（毫秒）
+    pub release_ms: f32,
+    /// 限幅器门限（dBFS，按峰值计算）
+    pub limiter_dbfs: f32,
+}
+
+impl Default for AgcParams {
+    fn default() -> Self {
+        Self {
+            target_dbfs: -18.0,
+            max_gain_db: 30.0,
+            attack_ms: 10.0,
+            release_ms: 500.0,
+            limiter_dbfs: -1.0,
+        }
+    }
+}
+
+/// 自动增益控制
+///
+/// 跟踪输入的短时 RMS 电平，把语音电平平滑地拉向目标 dBFS：
+/// 电平过高时按 `attack_ms` 快速降低增益，过低时按 `release_ms` 缓慢提升。
+/// 低于静音门限的输入不会改变增益，最大增益还受噪声底限制，避免把底噪放大成“语音”。
+/// 电平和增益按 10 ms 的块更新，块内线性插值。增益之后接一个瞬时启动的峰值限幅器，
+/// 保证输出不超过 `limiter_dbfs`。
+///
+/// AGC 位于 VAD 之前，使固定的 `vad_threshold` 适用于不同增益的麦克风。增益调整受
+/// 上一帧 VAD 判定控制（[`Agc::set_speech`]）：语音期间正常跟踪；静音期间保持增益，
+/// 只有电平明显高于噪声底（低增益麦克风上 VAD 尚未触发的语音）时才允许提升，
+/// 键盘声、关门声等不会把增益压低，停顿期间也不会把增益抬高。
+pub struct Agc {
+    params: AgcParams,
+    block_len: usize,
+    level_coef: f32,
+    attack_coef: f32,
+    release_coef: f32,
+    limiter_release_coef: f32,
+    noise_rise_per_sample: f32,
+    limit: f32,
+    /// 平滑后的输入功率；收到第一块之前为 `None`
+    level: Option<f32>,
+    /// 噪声底（dBFS）
+    noise_db: f32,
+    /// 当前块的目标增益（dB）
+    gain_db: f32,
+    /// 上一块结束时的增益（线性），块内从该值插值到目标增益
+    gain: f32,
+    /// 上一帧 VAD 是否判为语音
+    speech: bool,
+    /// 限幅器增益（线性）
+    limiter_gain: f32,
+}
+
+impl Agc {
+    pub fn new(sample_rate: u32, params: AgcParams) -> Self {
+        let coef = |ms: f32| (-1.0 / (ms.max(0.1) * 0.001 * sample_rate as f32)).exp();
+
+        Self {
+            params,
+            block_len: ((sample_rate as f32 * BLOCK_MS / 1000.0) as usize).max(1),
+            level_coef: coef(LEVEL_WINDOW_MS),
+            attack_coef: coef(params.attack_ms),
+            release_coef: coef(params.release_ms),
+            limiter_release_coef: coef(LIMITER_RELEASE_MS),
+            noise_rise_per_sample: NOISE_RISE_DB_PER_SECOND / sample_rate as f32,
+            limit: db_to_linear(params.limiter_dbfs).min(1.0),
+            level: None,
+            noise_db: MIN_LEVEL_DBFS,
+            gain_db: 0.0,
+            gain: 1.0,
+            speech: false,
+            limiter_gain: 1.0,
+        }
+    }
+
+    /// 当前增益（dB），用于调试输出
+    #[allow(dead_code)]
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// 设置上一帧的 VAD 判定，控制之后的增益调整
+    pub fn set_speech(&mut self, speech: bool) {
+        self.speech = speech;
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for block in samples.chunks_mut(self.block_len) {
+            self.update_gain(block);
+
+            let target = db_to_linear(self.gain_db);
+            let step = (target - self.gain) / block.len() as f32;
+            for sample in block.iter_mut() {
+                self.gain += step;
+                let y = *sample * self.gain;
+
+                // 峰值限幅：超出门限时立即压低，之后缓慢恢复
+                let peak = y.abs() * self.limiter_gain;
+                if peak > self.limit {
+                    self.limiter_gain = self.limit / y.abs();
+                } else {
+                    self.limiter_gain = self.limiter_release_coef * self.limiter_gain
+                        + (1.0 - self.limiter_release_coef);
+                }
+
+                *sample = (y * self.limiter_gain).clamp(-self.limit, self.limit);
+            }
+            self.gain = target;
+        }
+    }
+
+    /// 按一块的电平更新噪声底和目标增益
+    fn update_gain(&mut self, block: &[f32]) {
+        let n = block.len() as i32;
+        let power = block.iter().map(|x| x * x).sum::<f32>() / block.len() as f32;
+        let level = match self.level {
+            Some(level) => {
+                let coef = self.level_coef.powi(n);
+                coef * level + (1.0 - coef) * power
+            }
+            // 第一块同时作为噪声底的初值：假定开始采集时没有说话
+            None => {
+                self.noise_db = power_to_db(power);
+                power
+            }
+        };
+        self.level = Some(level);
+        let level_db = power_to_db(level);
+
+        if level_db < self.noise_db {
+            self.noise_db = level_db;
+        } else {
+            self.noise_db =
+                (self.noise_db + self.noise_rise_per_sample * block.len() as f32).min(level_db);
+        }
+
+        let above_noise = level_db > self.noise_db + SPEECH_MARGIN_DB;
+        if level_db > GATE_DBFS && (self.speech || above_noise) {
+            let noise_cap = (NOISE_CEILING_DBFS - self.noise_db).max(0.0);
+            let mut desired = (self.params.target_dbfs - level_db)
+                .clamp(-self.params.max_gain_db, self.params.max_gain_db)
+                .min(noise_cap);
+            // 语音中接近噪声底的部分（词间停顿）只允许降低增益
+            if !above_noise {
+                desired = desired.min(self.gain_db);
+            }
+            // VAD 判为静音时只允许提升，让低增益麦克风上的语音越过 VAD 阈值
+            if !self.speech {
+                desired = desired.max(self.gain_db);
+            }
+            let coef = if desired < self.gain_db {
+                self.attack_coef
+            } else {
+                self.release_coef
+            }
+            .powi(n);
+            self.gain_db = coef * self.gain_db + (1.0 - coef) * desired;
+        }
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn power_to_db(power: f32) -> f32 {
+    (10.0 * power.log10()).max(MIN_LEVEL_DBFS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms_db(samples: &[f32]) -> f32 {
+        let ms = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
+        10.0 * ms.log10()
+    }
+
+    fn sine(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f32 * 0.1).sin() * amplitude)
+            .collect()
+    }
+
+    /// 0.5 秒约 -73 dBFS 的底噪之后接语音
+    fn speech_after_silence(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut samples = sine(8000, 0.0003);
+        samples.extend(sine(len, amplitude));
+        samples
+    }
+
+    #[test]
+    fn test_boosts_quiet_speech() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        // 约 -43 dBFS
+        let mut samples = speech_after_silence(48000, 0.01);
+        agc.process(&mut samples);
+        assert!((rms_db(&samples[40000..]) + 18.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_attenuates_loud_speech() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        agc.set_speech(true);
+        // 约 -3 dBFS
+        let mut samples = sine(16000, 0.99);
+        agc.process(&mut samples);
+        assert!((rms_db(&samples[8000..]) + 18.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn test_does_not_amplify_silence() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        let mut samples = sine(16000, 0.001);
+        agc.process(&mut samples);
+        assert_eq!(agc.gain_db(), 0.0);
+        assert!(samples.iter().all(|s| s.abs() <= 0.001));
+    }
+
+    #[test]
+    fn test_limiter_prevents_clipping() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        // 先用安静语音把增益推高，再突然大声说话
+        let mut samples = speech_after_silence(32000, 0.01);
+        samples.extend(sine(8000, 0.9));
+        agc.process(&mut samples);
+
+        let limit = db_to_linear(-1.0);
+        assert!(samples.iter().all(|s| s.abs() <= limit + 1e-6));
+    }
+
+    #[test]
+    fn test_max_gain() {
+        let params = AgcParams {
+            max_gain_db: 6.0,
+            ..AgcParams::default()
+        };
+        let mut agc = Agc::new(16000, params);
+        let mut samples = speech_after_silence(48000, 0.01);
+        agc.process(&mut samples);
+        assert!(agc.gain_db() > 5.0);
+        assert!(agc.gain_db() <= 6.0 + 1e-3);
+    }
+
+    #[test]
+    fn test_does_not_boost_steady_noise() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        // 约 -49 dBFS 的稳定底噪不应被放大
+        let mut samples = sine(48000, 0.005);
+        agc.process(&mut samples);
+        assert!(agc.gain_db().abs() < 1e-3);
+        assert!((rms_db(&samples[32000..]) + 49.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_holds_gain_while_vad_is_silent() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        agc.set_speech(true);
+        let mut samples = speech_after_silence(48000, 0.01);
+        agc.process(&mut samples);
+        let gain = agc.gain_db();
+        assert!(gain > 20.0);
+
+        // VAD 判为静音：响亮的非语音不会压低增益，只由限幅器处理
+        agc.set_speech(false);
+        let mut samples = sine(8000, 0.5);
+        agc.process(&mut samples);
+        assert_eq!(agc.gain_db(), gain);
+        let limit = db_to_linear(-1.0);
+        assert!(samples.iter().all(|s| s.abs() <= limit + 1e-6));
+
+        // 同样的输入在语音期间会降低增益
+        agc.set_speech(true);
+        agc.process(&mut sine(8000, 0.5));
+        assert!(agc.gain_db() < gain - 10.0);
+    }
+
+    #[test]
+    fn test_noise_floor_limits_gain() {
+        let mut agc = Agc::new(16000, AgcParams::default());
+        // 约 -55 dBFS 的底噪：放大后不超过 -40 dBFS，最大增益约 15 dB
+        let mut samples = sine(8000, 0.0025);
+        samples.extend(sine(16000, 0.01));
+        agc.process(&mut samples);
+        assert!(agc.gain_db() > 8.0);
+        assert!(agc.gain_db() <= 15.5);
+    }
+}
@@ -0,0 +1,659 @@
+use crate::config::Config;
+use crate::downmix::{ChannelMode, Downmixer};
+use crate::monitor;
+use crate::ringbuf::{ring_buffer, Consumer, Producer};
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Paraformer 模型要求的采样率
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// 流错误回调写入的最近一次错误信息
+type ErrorSlot = Arc<Mutex<Option<String>>>;
+
+/// 超过该时长没有收到音频数据，视为设备已消失
+const STALL_TIMEOUT: Duration = Duration::from_secs(2);
+/// 重连尝试间隔；使用默认设备期间也按此间隔检查首选设备是否恢复
+const RETRY_INTERVAL: Duration = Duration::from_secs(2);
+/// 采集环形缓冲区可容纳的音频时长（秒）
+const RING_SECONDS: u32 = 2;
+/// 回调缓冲区预分配的帧数；超过时才会在回调中扩容
+const CALLBACK_FRAMES: usize = 8192;
+
+/// 按优先级排列的可用采样格式：同等条件下优先浮点，其次高位宽整数
+const PREFERRED_FORMATS: [SampleFormat; 10] = [
+    SampleFormat::F32,
+    SampleFormat::I32,
+    SampleFormat::I16,
+    SampleFormat::U16,
+    SampleFormat::F64,
+    SampleFormat::I64,
+    SampleFormat::U32,
+    SampleFormat::U64,
+    SampleFormat::I8,
+    SampleFormat::U8,
+];
+
+/// 协商得到的输入流配置
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    pub stream_config: cpal::StreamConfig,
+    pub sample_format: SampleFormat,
+}
+
+impl CaptureConfig {
+    pub fn sample_rate(&self) -> u32 {
+        self.stream_config.sample_rate.0
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.stream_config.channels
+    }
+
+    pub fn needs_resampling(&self) -> bool {
+        self.sample_rate() != TARGET_SAMPLE_RATE
+    }
+}
+
+/// 按名称选择音频后端（不区分大小写），未指定时使用系统默认后端
+///
+/// cpal 没有原生的 PipeWire 后端：`pipewire` 会优先映射到 JACK（PipeWire
+/// 提供 JACK 接口，节点可直接作为设备选择），JACK 不可用时退回 ALSA
+/// （通过 PipeWire 的 ALSA 插件访问）。
+pub fn select_host(name: Option<&str>) -> Result<cpal::Host> {
+    let Some(name) = name else {
+        return Ok(cpal::default_host());
+    };
+
+    let available = cpal::available_hosts();
+    let id = if name.eq_ignore_ascii_case("pipewire") {
+        ["JACK", "ALSA"]
+            .iter()
+            .find_map(|n| available.iter().find(|id| id.name() == *n))
+            .copied()
+    } else {
+        available
+            .iter()
+            .find(|id| id.name().eq_ignore_ascii_case(name))
+            .copied()
+    };
+
+    match id {
+        Some(id) => Ok(cpal::host_from_id(id)?),
+        None => {
+            let names: Vec<&str> = available.iter().map(|id| id.name()).collect();
+            if cpal::ALL_HOSTS
+                .iter()
+                .any(|id| id.name().eq_ignore_ascii_case(name))
+            {
+                anyhow::bail!("音频后端 {} 当前不可用（可用: {}）", name, names.join(", "))
+            }
+            anyhow::bail!("未知的音频后端: {}（可用: {}）", name, names.join(", "))
+        }
+    }
+}
+
+/// 按索引或名称选择输入设备，都未指定时使用默认设备
+pub fn select_device(
+    host: &cpal::Host,
+    index: Option<usize>,
+    name: Option<&str>,
+) -> Result<cpal::Device> {
+    if let Some(idx) = index {
+        host.input_devices()?
+            .nth(idx)
+            .context(format!("设备索引 {} 无效", idx))
+    } else if let Some(name) = name {
+        host.input_devices()?
+            .find(|d| d.name().ok().as_deref() == Some(name))
+            .context(format!("未找到设备名称: {}", name))
+    } else {
+        host.default_input_device().context("未找到默认输入设备")
+    }
+}
+
+/// 协商输入流配置
+///
+/// 优先使用设备原生支持的 16 kHz 单声道配置；否则使用设备默认配置并在
+/// 之后重采样。采样格式保持设备原生格式，在采集回调中统一转换为 f32。
+/// `min_channels` 大于 1 时（例如指定了第 2 声道）跳过单声道配置。
+pub fn negotiate_config(device: &cpal::Device, min_channels: u16) -> Result<CaptureConfig> {
+    let default_config = device.default_input_config()?;
+    let native_format = default_config.sample_format();
+
+    let supported = device
+        .supported_input_configs()
+        .ok()
+        .filter(|_| min_channels <= 1);
+    let mono_16khz = supported.and_then(|configs| {
+        configs
+            .filter(|c| c.channels() == 1)
+            .filter(|c| is_supported_format(c.sample_format()))
+            .filter(|c| {
+                c.min_sample_rate().0 <= TARGET_SAMPLE_RATE
+                    && TARGET_SAMPLE_RATE <= c.max_sample_rate().0
+            })
+            .min_by_key(|c| format_rank(c.sample_format(), native_format))
+    });
+
+    if let Some(range) = mono_16khz {
+        return Ok(CaptureConfig {
+            stream_config: cpal::StreamConfig {
+                channels: 1,
+                sample_rate: cpal::SampleRate(TARGET_SAMPLE_RATE),
+                buffer_size: cpal::BufferSize::Default,
+            },
+            sample_format: range.sample_format(),
+        });
+    }
+
+    if !is_supported_format(native_format) {
+        anyhow::bail!("不支持的采样格式: {:?}", native_format);
+    }
+
+    Ok(CaptureConfig {
+        stream_config: cpal::StreamConfig {
+            channels: default_config.channels(),
+            sample_rate: default_config.sample_rate(),
+            buffer_size: cpal::BufferSize::Default,
+        },
+        sample_format: native_format,
+    })
+}
+
+/// 构建输入流，原生样本在回调中转换为 f32 后交给 `on_data`，
+/// 流错误交给 `on_error`
+///
+/// 转换缓冲区预先分配，通常情况下回调中不会分配内存。
+pub fn build_capture_stream<F, E>(
+    device: &cpal::Device,
+    capture: &CaptureConfig,
+    on_data: F,
+    on_error: E,
+) -> Result<cpal::Stream>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    let config = &capture.stream_config;
+    let stream = match capture.sample_format {
+        SampleFormat::F32 => build_stream::<f32, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::F64 => build_stream::<f64, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::I8 => build_stream::<i8, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::I16 => build_stream::<i16, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::I32 => build_stream::<i32, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::I64 => build_stream::<i64, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::U8 => build_stream::<u8, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::U16 => build_stream::<u16, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::U32 => build_stream::<u32, F, E>(device, config, on_data, on_error)?,
+        SampleFormat::U64 => build_stream::<u64, F, E>(device, config, on_data, on_error)?,
+        other => anyhow::bail!("不支持的采样格式: {:?}", other),
+    };
+    Ok(stream)
+}
+
+fn build_stream<T, F, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_data: F,
+    on_error: E,
+) -> Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32]) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    let mut converted = Vec::with_capacity(CALLBACK_FRAMES * config.channels as usize);
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            convert_samples(data, &mut converted);
+            on_data(&converted);
+        },
+        on_error,
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// 音频设备连接状态
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceStatus {
+    /// 已连接首选设备
+    Connected { name: String },
+    /// 设备断开或出错，正在重连
+    Reconnecting { reason: String },
+    /// 首选设备不可用，暂时使用默认设备
+    Fallback { name: String },
+}
+
+impl fmt::Display for DeviceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connected { name } => write!(f, "已连接: {}", name),
+            Self::Reconnecting { reason } => write!(f, "设备断开，正在重连（{}）", reason),
+            Self::Fallback { name } => write!(f, "首选设备不可用，使用默认设备: {}", name),
+        }
+    }
+}
+
+/// 采集统计信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureStats {
+    /// 环形缓冲区溢出次数（处理线程跟不上采集速度）
+    pub overruns: u64,
+    /// 因溢出丢弃的样本数
+    pub dropped_samples: u64,
+}
+
+impl fmt::Display for CaptureStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "缓冲区溢出 {} 次，丢弃 {} 个样本",
+            self.overruns, self.dropped_samples
+        )
+    }
+}
+
+/// 采集选项，来自命令行参数和配置文件
+#[derive(Debug, Clone, Default)]
+pub struct CaptureOptions {
+    /// 音频后端名称，`None` 表示系统默认后端
+    pub host: Option<String>,
+    pub device_index: Option<usize>,
+    pub device_name: Option<String>,
+    /// PulseAudio / PipeWire 监视源（系统音频），设置后忽略设备索引和名称
+    pub monitor: Option<String>,
+    /// 首选设备不可用时不退回默认设备（多设备采集时避免两路来源读同一设备）
+    pub no_fallback: bool,
+    pub input_channels: Vec<u16>,
+    pub channel_mode: ChannelMode,
+}
+
+impl CaptureOptions {
+    pub fn from_config(
+        config: &Config,
+        device_index: Option<usize>,
+        device_name: Option<String>,
+    ) -> Self {
+        Self {
+            host: config.host.clone(),
+            device_index,
+            device_name,
+            monitor: None,
+            no_fallback: false,
+            input_channels: config.input_channels.clone(),
+            channel_mode: config.channel_mode,
+        }
+    }
+}
+
+/// 带自动重连的音频采集
+///
+/// 采集回调把单声道 f32 数据写入预分配的无锁环形缓冲区（回调中不加锁、
+/// 不分配内存），由 [`AudioCapture::try_recv`] 读取。处理线程跟不上时新数据会被丢弃，并计入 [`AudioCapture::stats`]。
+/// 调用方需要定期调用 [`AudioCapture::poll`]：流出错（例如拔出 USB 耳机、
+/// 蓝牙切换配置）或长时间没有数据时会关闭当前流，之后按固定间隔重新打开
+/// 首选设备；首选设备不可用时退回默认设备，并在首选设备恢复后切换回去。
+pub struct AudioCapture {
+    options: CaptureOptions,
+    host_id: cpal::HostId,
+    /// 首选设备名称；未指定设备时为 `None`，表示始终跟随默认设备
+    preferred: Option<String>,
+    stream: Option<cpal::Stream>,
+    capture: CaptureConfig,
+    device_name: String,
+    consumer: Consumer,
+    /// 已替换掉的流累计的溢出统计
+    past_stats: CaptureStats,
+    error: ErrorSlot,
+    data_seen: Arc<AtomicBool>,
+    last_data: Instant,
+    last_attempt: Instant,
+    status: DeviceStatus,
+    /// 已切换到新的流，调用方尚未通过 [`AudioCapture::take_reopened`] 取走
+    reopened: bool,
+}
+
+impl AudioCapture {
+    /// 打开首选设备并开始采集
+    pub fn open(options: CaptureOptions) -> Result<Self> {
+        let host = select_host(options.host.as_deref())?;
+        let device = match options.monitor {
+            Some(_) => monitor::sound_server_device(&host)?,
+            None => select_device(&host, options.device_index, options.device_name.as_deref())?,
+        };
+        let device_name = device.name().unwrap_or_else(|_| "未知设备".to_string());
+        let preferred = if options.monitor.is_some()
+            || options.device_index.is_some()
+            || options.device_name.is_some()
+        {
+            Some(device_name.clone())
+        } else {
+            None
+        };
+
+        let data_seen = Arc::new(AtomicBool::new(false));
+        let (stream, capture, consumer, error) = start_stream(&device, &options, &data_seen)?;
+
+        Ok(Self {
+            options,
+            host_id: host.id(),
+            preferred,
+            stream: Some(stream),
+            capture,
+            status: DeviceStatus::Connected {
+                name: device_name.clone(),
+            },
+            device_name,
+            consumer,
+            past_stats: CaptureStats::default(),
+            error,
+            data_seen,
+            last_data: Instant::now(),
+            last_attempt: Instant::now(),
+            reopened: false,
+        })
+    }
+
+    /// 读取已采集的全部样本；没有数据时返回 `None`
+    pub fn try_recv(&mut self) -> Option<Vec<f32>> {
+        if self.consumer.is_empty() {
+            return None;
+        }
+        let mut samples = Vec::with_capacity(self.consumer.len());
+        self.consumer.pop_into(&mut samples);
+        Some(samples)
+    }
+
+    /// 丢弃已采集但尚未读取的样本，暂不需要音频时定期调用，避免缓冲区溢出
+    pub fn discard(&mut self) {
+        self.consumer.clear();
+    }
+
+    /// 自打开以来的累计采集统计（包括重连前的流）
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            overruns: self.past_stats.overruns + self.consumer.overruns(),
+            dropped_samples: self.past_stats.dropped_samples + self.consumer.dropped_samples(),
+        }
+    }
+
+    /// 当前流的配置；重连后可能变化（例如退回默认设备）
+    pub fn config(&self) -> &CaptureConfig {
+        &self.capture
+    }
+
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    pub fn options(&self) -> &CaptureOptions {
+        &self.options
+    }
+
+    /// 音频后端名称
+    pub fn host_name(&self) -> &'static str {
+        self.host_id.name()
+    }
+
+    pub fn status(&self) -> &DeviceStatus {
+        &self.status
+    }
+
+    /// 检查流状态并在需要时重连
+    ///
+    /// 状态发生变化时返回新状态。
+    pub fn poll(&mut self) -> Option<DeviceStatus> {
+        if self.data_seen.swap(false, Ordering::Relaxed) {
+            self.last_data = Instant::now();
+        }
+
+        if self.stream.is_some() {
+            let error = self.error.lock().unwrap().take();
+            let reason = error.or_else(|| {
+                (self.last_data.elapsed() > STALL_TIMEOUT).then(|| "长时间没有音频数据".to_string())
+            });
+            if let Some(reason) = reason {
+                self.stream = None;
+                self.last_attempt = Instant::now();
+                return self.set_status(DeviceStatus::Reconnecting { reason });
+            }
+        }
+
+        if self.last_attempt.elapsed() < RETRY_INTERVAL {
+            return None;
+        }
+
+        match self.status {
+            DeviceStatus::Connected { .. } => None,
+            DeviceStatus::Reconnecting { .. } | DeviceStatus::Fallback { .. } => {
+                self.last_attempt = Instant::now();
+                self.reconnect()
+            }
+        }
+    }
+
+    fn reconnect(&mut self) -> Option<DeviceStatus> {
+        let host = cpal::host_from_id(self.host_id).ok()?;
+
+        // 优先尝试首选设备（按名称匹配，设备索引在热插拔后可能变化）
+        let preferred = match &self.preferred {
+            Some(name) => host
+                .input_devices()
+                .ok()
+                .and_then(|mut devices| devices.find(|d| d.name().ok().as_ref() == Some(name))),
+            None => host.default_input_device(),
+        };
+        if let Some(device) = preferred {
+            if self.switch_to(&device).is_ok() {
+                let name = self.device_name.clone();
+                return self.set_status(DeviceStatus::Connected { name });
+            }
+        }
+
+        // 首选设备不可用时退回默认设备；监视源没有可替代的设备
+        if self.preferred.is_some()
+            && self.options.monitor.is_none()
+            && !self.options.no_fallback
+            && !matches!(self.status, DeviceStatus::Fallback { .. })
+        {
+            if let Some(device) = host.default_input_device() {
+                if self.switch_to(&device).is_ok() {
+                    let name = self.device_name.clone();
+                    return self.set_status(DeviceStatus::Fallback { name });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// 是否已切换到新的流（重连或切换设备），读取后清除
+    ///
+    /// 新流的采样率可能不同：返回 `true` 后调用方需要按 [`AudioCapture::config`]
+    /// 重建重采样器等依赖采样率的状态。旧流中尚未读取的样本已随旧缓冲区丢弃，
+    /// 之后 [`AudioCapture::try_recv`] 只返回新流的数据。
+    pub fn take_reopened(&mut self) -> bool {
+        std::mem::take(&mut self.reopened)
+    }
+
+    /// 打开新设备；成功后才替换当前流，失败时保留原有流
+    fn switch_to(&mut self, device: &cpal::Device) -> Result<()> {
+        let (stream, capture, consumer, error) =
+            start_stream(device, &self.options, &self.data_seen)?;
+        // 新流已经启动，旧流在下面赋值时才停止，两者会短暂同时运行；
+        // 每个流写入独立的环形缓冲区，每个缓冲区仍然只有一个生产者。
+        // 旧缓冲区中按旧采样率采集、尚未读取的样本随之丢弃
+        self.stream = Some(stream);
+        self.past_stats = self.stats();
+        self.consumer = consumer;
+        self.reopened = true;
+        self.capture = capture;
+        self.error = error;
+        self.device_name = device.name().unwrap_or_else(|_| "未知设备".to_string());
+        self.last_data = Instant::now();
+        Ok(())
+    }
+
+    fn set_status(&mut self, status: DeviceStatus) -> Option<DeviceStatus> {
+        if self.status == status {
+            return None;
+        }
+        self.status = status.clone();
+        Some(status)
+    }
+}
+
+type StartedStream = (cpal::Stream, CaptureConfig, Consumer, ErrorSlot);
+
+fn start_stream(
+    device: &cpal::Device,
+    options: &CaptureOptions,
+    data_seen: &Arc<AtomicBool>,
+) -> Result<StartedStream> {
+    match &options.monitor {
+        Some(source) => monitor::open_on_source(source, || open_stream(device, options, data_seen)),
+        None => open_stream(device, options, data_seen),
+    }
+}
+
+fn open_stream(
+    device: &cpal::Device,
+    options: &CaptureOptions,
+    data_seen: &Arc<AtomicBool>,
+) -> Result<StartedStream> {
+    let capture = negotiate_config(
+        device,
+        Downmixer::required_channels(&options.input_channels),
+    )?;
+    let downmixer = Downmixer::new(
+        capture.channels(),
+        &options.input_channels,
+        options.channel_mode,
+    )?;
+
+    let (mut producer, consumer): (Producer, Consumer) =
+        ring_buffer((capture.sample_rate() * RING_SECONDS) as usize);
+    let mut mono_data = Vec::with_capacity(CALLBACK_FRAMES);
+    let data_seen = Arc::clone(data_seen);
+    let error = Arc::new(Mutex::new(None));
+    let error_slot = Arc::clone(&error);
+
+    let stream = build_capture_stream(
+        device,
+        &capture,
+        // 实时线程：不加锁、不打印、不分配内存
+        move |data: &[f32]| {
+            data_seen.store(true, Ordering::Relaxed);
+            downmixer.process(data, &mut mono_data);
+            producer.push_slice(&mono_data);
+        },
+        move |err| {
+            eprintln!("错误：{}", err);
+            *error_slot.lock().unwrap() = Some(err.to_string());
+        },
+    )?;
+    stream.play()?;
+
+    Ok((stream, capture, consumer, error))
+}
+
+/// 将原生样本转换为 [-1.0, 1.0] 范围的 f32，复用 `out` 的缓冲区
+pub fn convert_samples<T>(input: &[T], out: &mut Vec<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    out.clear();
+    out.extend(input.iter().map(|&s| s.to_sample::<f32>()));
+}
+
+fn is_supported_format(format: SampleFormat) -> bool {
+    PREFERRED_FORMATS.contains(&format)
+}
+
+/// 格式优先级：设备原生格式最优，其余按 `PREFERRED_FORMATS` 顺序
+fn format_rank(format: SampleFormat, native: SampleFormat) -> usize {
+    if format == native {
+        return 0;
+    }
+    PREFERRED_FORMATS
+        .iter()
+        .position(|&f| f == format)
+        .map_or(usize::MAX, |p| p + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_i16() {
+        let mut out = Vec::new();
+        convert_samples(&[0i16, i16::MAX, i16::MIN, 16384], &mut out);
+        assert_eq!(out[0], 0.0);
+        assert!((out[1] - 1.0).abs() < 1e-4);
+        assert_eq!(out[2], -1.0);
+        assert!((out[3] - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_convert_u16() {
+        let mut out = Vec::new();
+        convert_samples(&[32768u16, 0, u16::MAX], &mut out);
+        assert_eq!(out[0], 0.0);
+        assert_eq!(out[1], -1.0);
+        assert!((out[2] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_convert_i32() {
+        let mut out = Vec::new();
+        convert_samples(&[0i32, i32::MAX, i32::MIN / 2], &mut out);
+        assert_eq!(out[0], 0.0);
+        assert!((out[1] - 1.0).abs() < 1e-6);
+        assert!((out[2] + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_convert_reuses_buffer() {
+        let mut out = vec![9.0; 8];
+        convert_samples(&[0.25f32, -0.25], &mut out);
+        assert_eq!(out, vec![0.25, -0.25]);
+    }
+
+    #[test]
+    fn test_device_status_display() {
+        let status = DeviceStatus::Reconnecting {
+            reason: "设备已断开".to_string(),
+        };
+        assert!(status.to_string().contains("设备已断开"));
+        let status = DeviceStatus::Fallback {
+            name: "default".to_string(),
+        };
+        assert!(status.to_string().contains("default"));
+    }
+
+    #[test]
+    fn test_select_unknown_host() {
+        let err = select_host(Some("nonexistent")).err().unwrap();
+        assert!(err.to_string().contains("未知的音频后端"));
+    }
+
+    #[test]
+    fn test_format_rank_prefers_native() {
+        assert_eq!(format_rank(SampleFormat::I16, SampleFormat::I16), 0);
+        assert!(
+            format_rank(SampleFormat::F32, SampleFormat::I16)
+                < format_rank(SampleFormat::U16, SampleFormat::I16)
+        );
+    }
+}
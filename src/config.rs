@@ -1,15 +1,171 @@
+use crate::agc::AgcParams;
+use crate::downmix::ChannelMode;
+use crate::endpoint::EndpointStrategy;
+use crate::resampler::ResamplerKind;
+use crate::source::{CaptureSource, SourceConfig};
+use crate::vad::{EndpointParams, VadParams};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 未通过 `--config` 指定时使用的配置文件
+pub const DEFAULT_CONFIG_PATH: &str = "./config.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     #[serde(default = "default_model_dir")]
     pub model_dir: String,
     #[serde(default = "default_vad_threshold")]
     pub vad_threshold: f32,
+    /// 持续跟踪噪声底，让 VAD 阈值随环境变化（从 vad_threshold 出发）
+    #[serde(default)]
+    pub vad_adaptive: bool,
+    /// 阈值相对噪声底的余量（dB），用于 `calibrate` 和自适应阈值
+    #[serde(default = "default_vad_margin_db")]
+    pub vad_margin_db: f32,
+    /// VAD 分析帧长（毫秒，10–30），判定结果与设备缓冲大小无关
+    #[serde(default = "default_vad_frame_ms")]
+    pub vad_frame_ms: u32,
+    /// 语音结束阈值比 vad_threshold 低多少（dB）
+    #[serde(default = "default_vad_hysteresis_db")]
+    pub vad_hysteresis_db: f32,
+    /// 连续超过阈值多长时间才判为语音开始（毫秒），滤除咔嗒声等短促噪声
+    #[serde(default = "default_vad_min_speech_ms")]
+    pub vad_min_speech_ms: u32,
+    /// 低于结束阈值后仍保持语音状态的时长（毫秒），跨过词间停顿；
+    /// GUI 语音门限在拖尾期内继续放行音频，保留句尾的弱辅音。
+    /// 旧名称 `hangover_ms` 已弃用，仍作为别名读取
+    #[serde(default = "default_vad_hangover_ms", alias = "hangover_ms")]
+    pub vad_hangover_ms: u32,
+    /// 语音后连续静音达到该时长（毫秒）即断句
+    #[serde(default = "default_endpoint_min_silence_ms")]
+    pub endpoint_min_silence_ms: u32,
+    /// 语音累计达到该时长（毫秒）才允许断句
+    #[serde(default = "default_endpoint_min_speech_ms")]
+    pub endpoint_min_speech_ms: u32,
+    /// 语句最长时长（毫秒），超过后强制断句；0 表示不限制
+    #[serde(default = "default_endpoint_max_utterance_ms")]
+    pub endpoint_max_utterance_ms: u32,
+    /// 断句策略：energy（默认）、recognizer、neural 或 hybrid
+    #[serde(default)]
+    pub endpoint_strategy: EndpointStrategy,
+    /// 将每次断句判定及原因输出到标准错误，用于调整断句参数
+    #[serde(default)]
+    pub endpoint_log: bool,
+    /// silero VAD 模型路径（silero_vad.onnx），neural / hybrid 策略使用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vad_model: Option<String>,
+    /// 神经网络 VAD 的语音概率阈值
+    #[serde(default = "default_vad_model_threshold")]
+    pub vad_model_threshold: f32,
     #[serde(default = "default_hotkey")]
     pub hotkey: String,
+    /// 语音开始前保留的预录音频时长（毫秒），避免句首被 VAD 截断
+    #[serde(default = "default_pre_roll_ms")]
+    pub pre_roll_ms: u32,
+    /// 音频后端：alsa、jack 或 pipewire；未设置时使用系统默认后端
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// 采集来源：mic（默认）、loopback（系统音频）或 both（分别识别并标注来源）
+    #[serde(default)]
+    pub capture_source: CaptureSource,
+    /// 系统音频监视源：default 表示默认输出设备，或 PulseAudio / PipeWire 源名称
+    #[serde(default = "default_loopback_source")]
+    pub loopback_source: String,
+    /// capture_source = "both" 时麦克风结果的标签
+    #[serde(default = "default_mic_label")]
+    pub mic_label: String,
+    /// capture_source = "both" 时系统音频结果的标签
+    #[serde(default = "default_loopback_label")]
+    pub loopback_label: String,
+    /// 同时采集的多路输入（`[[sources]]`），设置后忽略 capture_source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<SourceConfig>,
+    /// 语句录音目录，设置后每个语句保存为 WAV + JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_dir: Option<String>,
+    /// 字幕每行最大宽度（列），中日韩文字按两列计
+    #[serde(default = "default_subtitle_max_line_width")]
+    pub subtitle_max_line_width: usize,
+    /// 每条字幕最多行数
+    #[serde(default = "default_subtitle_max_lines")]
+    pub subtitle_max_lines: usize,
+    /// 每条字幕最长显示时长（毫秒）
+    #[serde(default = "default_subtitle_max_cue_ms")]
+    pub subtitle_max_cue_ms: u32,
+    /// 重采样算法：sinc（默认）或 linear
+    #[serde(default)]
+    pub resampler: ResamplerKind,
+    /// 使用的输入声道（从 1 开始），为空表示全部声道
+    #[serde(default)]
+    pub input_channels: Vec<u16>,
+    /// 多声道合并方式：average（默认）或 loudest
+    #[serde(default)]
+    pub channel_mode: ChannelMode,
+    /// 高通滤波截止频率（Hz），去除直流偏置和低频隆隆声；0 表示关闭
+    #[serde(default = "default_highpass_hz")]
+    pub highpass_hz: f32,
+    /// 启用谱减法降噪
+    #[serde(default)]
+    pub denoise: bool,
+    /// 降噪强度，0.0 到 1.0
+    #[serde(default = "default_denoise_strength")]
+    pub denoise_strength: f32,
+    /// 旁路降噪（保留降噪阶段但直接输出原始音频，用于 A/B 对比）
+    #[serde(default)]
+    pub denoise_bypass: bool,
+    /// 启用自动增益控制
+    #[serde(default)]
+    pub agc: bool,
+    /// AGC 目标语音电平（dBFS）
+    #[serde(default = "default_agc_target_dbfs")]
+    pub agc_target_dbfs: f32,
+    /// AGC 最大增益（dB）
+    #[serde(default = "default_agc_max_gain_db")]
+    pub agc_max_gain_db: f32,
+    /// AGC 增益下降的响应时间（毫秒）
+    #[serde(default = "default_agc_attack_ms")]
+    pub agc_attack_ms: f32,
+    /// AGC 增益回升的响应时间（毫秒）
+    #[serde(default = "default_agc_release_ms")]
+    pub agc_release_ms: f32,
+    /// 限幅器门限（dBFS）
+    #[serde(default = "default_agc_limiter_dbfs")]
+    pub agc_limiter_dbfs: f32,
+    /// 默认使用的配置档，可被 `--profile` 覆盖
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// 配置档：`[profiles.<名称>]` 下的设置覆盖基础配置
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// 配置档，未设置的字段沿用基础配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highpass_hz: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoise: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoise_strength: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoise_bypass: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agc: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agc_target_dbfs: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agc_max_gain_db: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_min_silence_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_min_speech_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_max_utterance_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_strategy: Option<EndpointStrategy>,
 }
 
 fn default_model_dir() -> String {
@@ -20,16 +176,147 @@ fn default_vad_threshold() -> f32 {
     0.01
 }
 
+fn default_vad_margin_db() -> f32 {
+    10.0
+}
+
+fn default_vad_frame_ms() -> u32 {
+    VadParams::default().frame_ms
+}
+
+fn default_vad_hysteresis_db() -> f32 {
+    VadParams::default().hysteresis_db
+}
+
+fn default_vad_min_speech_ms() -> u32 {
+    VadParams::default().min_speech_ms
+}
+
+fn default_vad_hangover_ms() -> u32 {
+    VadParams::default().hangover_ms
+}
+
+fn default_endpoint_min_silence_ms() -> u32 {
+    EndpointParams::default().min_silence_ms
+}
+
+fn default_endpoint_min_speech_ms() -> u32 {
+    EndpointParams::default().min_speech_ms
+}
+
+fn default_endpoint_max_utterance_ms() -> u32 {
+    EndpointParams::default().max_utterance_ms
+}
+
+fn default_vad_model_threshold() -> f32 {
+    0.5
+}
+
+fn default_subtitle_max_line_width() -> usize {
+    42
+}
+
+fn default_subtitle_max_lines() -> usize {
+    2
+}
+
+fn default_subtitle_max_cue_ms() -> u32 {
+    6000
+}
+
 fn default_hotkey() -> String {
     "F3".to_string()
 }
 
+fn default_loopback_source() -> String {
+    "default".to_string()
+}
+
+fn default_mic_label() -> String {
+    "me".to_string()
+}
+
+fn default_loopback_label() -> String {
+    "remote".to_string()
+}
+
+fn default_pre_roll_ms() -> u32 {
+    300
+}
+
+fn default_highpass_hz() -> f32 {
+    80.0
+}
+
+fn default_denoise_strength() -> f32 {
+    0.5
+}
+
+fn default_agc_target_dbfs() -> f32 {
+    AgcParams::default().target_dbfs
+}
+
+fn default_agc_max_gain_db() -> f32 {
+    AgcParams::default().max_gain_db
+}
+
+fn default_agc_attack_ms() -> f32 {
+    AgcParams::default().attack_ms
+}
+
+fn default_agc_release_ms() -> f32 {
+    AgcParams::default().release_ms
+}
+
+fn default_agc_limiter_dbfs() -> f32 {
+    AgcParams::default().limiter_dbfs
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             model_dir: default_model_dir(),
             vad_threshold: default_vad_threshold(),
+            vad_adaptive: false,
+            vad_margin_db: default_vad_margin_db(),
+            vad_frame_ms: default_vad_frame_ms(),
+            vad_hysteresis_db: default_vad_hysteresis_db(),
+            vad_min_speech_ms: default_vad_min_speech_ms(),
+            vad_hangover_ms: default_vad_hangover_ms(),
+            endpoint_min_silence_ms: default_endpoint_min_silence_ms(),
+            endpoint_min_speech_ms: default_endpoint_min_speech_ms(),
+            endpoint_max_utterance_ms: default_endpoint_max_utterance_ms(),
+            endpoint_strategy: EndpointStrategy::default(),
+            endpoint_log: false,
+            vad_model: None,
+            vad_model_threshold: default_vad_model_threshold(),
             hotkey: default_hotkey(),
+            pre_roll_ms: default_pre_roll_ms(),
+            host: None,
+            capture_source: CaptureSource::default(),
+            loopback_source: default_loopback_source(),
+            mic_label: default_mic_label(),
+            loopback_label: default_loopback_label(),
+            sources: Vec::new(),
+            record_dir: None,
+            subtitle_max_line_width: default_subtitle_max_line_width(),
+            subtitle_max_lines: default_subtitle_max_lines(),
+            subtitle_max_cue_ms: default_subtitle_max_cue_ms(),
+            resampler: ResamplerKind::default(),
+            input_channels: Vec::new(),
+            channel_mode: ChannelMode::default(),
+            highpass_hz: default_highpass_hz(),
+            denoise: false,
+            denoise_strength: default_denoise_strength(),
+            denoise_bypass: false,
+            agc: false,
+            agc_target_dbfs: default_agc_target_dbfs(),
+            agc_max_gain_db: default_agc_max_gain_db(),
+            agc_attack_ms: default_agc_attack_ms(),
+            agc_release_ms: default_agc_release_ms(),
+            agc_limiter_dbfs: default_agc_limiter_dbfs(),
+            profile: None,
+            profiles: HashMap::new(),
         }
     }
 }
@@ -40,8 +327,94 @@ impl Config {
             return Ok(Self::default());
         }
         let content = std::fs::read_to_string(path)?;
+        let table: toml::Table = toml::from_str(&content)?;
+        if table.contains_key("hangover_ms") {
+            eprintln!("⚠️  配置项 hangover_ms 已弃用，请改用 vad_hangover_ms");
+        }
         Ok(toml::from_str(&content)?)
     }
+
+    /// 加载命令行指定的配置文件；未指定时尝试 `./config.toml`，失败则使用默认配置
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::load(Path::new(DEFAULT_CONFIG_PATH)).unwrap_or_default()),
+        }
+    }
+
+    /// 将顶层设置写入配置文件，保留文件中已有的注释和其他设置；文件不存在时创建
+    pub fn save_values(path: &Path, values: &[(&str, toml_edit::Value)]) -> Result<()> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut document: toml_edit::DocumentMut = content.parse()?;
+        for (key, value) in values {
+            document[key] = toml_edit::value(value.clone());
+        }
+        std::fs::write(path, document.to_string())?;
+        Ok(())
+    }
+
+    /// AGC 参数
+    pub fn agc_params(&self) -> AgcParams {
+        AgcParams {
+            target_dbfs: self.agc_target_dbfs,
+            max_gain_db: self.agc_max_gain_db,
+            attack_ms: self.agc_attack_ms,
+            release_ms: self.agc_release_ms,
+            limiter_dbfs: self.agc_limiter_dbfs,
+        }
+    }
+
+    /// 应用配置档；`name` 为 `None` 时使用配置文件中的 `profile`
+    pub fn apply_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let Some(name) = name.map(str::to_string).or_else(|| self.profile.clone()) else {
+            return Ok(());
+        };
+        let profile = self
+            .profiles
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("未找到配置档: {}", name))?;
+
+        if let Some(cutoff) = profile.highpass_hz {
+            self.highpass_hz = cutoff;
+        }
+        if let Some(denoise) = profile.denoise {
+            self.denoise = denoise;
+        }
+        if let Some(strength) = profile.denoise_strength {
+            self.denoise_strength = strength;
+        }
+        if let Some(bypass) = profile.denoise_bypass {
+            self.denoise_bypass = bypass;
+        }
+        if let Some(agc) = profile.agc {
+            self.agc = agc;
+        }
+        if let Some(target) = profile.agc_target_dbfs {
+            self.agc_target_dbfs = target;
+        }
+        if let Some(max_gain) = profile.agc_max_gain_db {
+            self.agc_max_gain_db = max_gain;
+        }
+        if let Some(ms) = profile.endpoint_min_silence_ms {
+            self.endpoint_min_silence_ms = ms;
+        }
+        if let Some(ms) = profile.endpoint_min_speech_ms {
+            self.endpoint_min_speech_ms = ms;
+        }
+        if let Some(ms) = profile.endpoint_max_utterance_ms {
+            self.endpoint_max_utterance_ms = ms;
+        }
+        if let Some(strategy) = profile.endpoint_strategy {
+            self.endpoint_strategy = strategy;
+        }
+        self.profile = Some(name);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -55,6 +428,10 @@ mod tests {
         assert_eq!(config.model_dir, "./models");
         assert_eq!(config.vad_threshold, 0.01);
         assert_eq!(config.hotkey, "F3");
+        assert!(config.record_dir.is_none());
+        assert_eq!(config.resampler, ResamplerKind::Sinc);
+        assert!(config.input_channels.is_empty());
+        assert_eq!(config.channel_mode, ChannelMode::Average);
     }
 
     #[test]
@@ -77,4 +454,236 @@ mod tests {
         assert_eq!(config.vad_threshold, 0.05);
         assert_eq!(config.hotkey, "F4");
     }
+
+    #[test]
+    fn test_config_record_dir() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "record_dir = \"/tmp/cinnabar-rec\"").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.record_dir.as_deref(), Some("/tmp/cinnabar-rec"));
+    }
+
+    #[test]
+    fn test_config_resampler() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "resampler = \"linear\"").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.resampler, ResamplerKind::Linear);
+    }
+
+    #[test]
+    fn test_config_channels() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "input_channels = [2]").unwrap();
+        writeln!(temp_file, "channel_mode = \"loudest\"").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.input_channels, vec![2]);
+        assert_eq!(config.channel_mode, ChannelMode::Loudest);
+    }
+
+    #[test]
+    fn test_config_profiles() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "denoise = true").unwrap();
+        writeln!(temp_file, "profile = \"home\"").unwrap();
+        writeln!(temp_file, "[profiles.home]").unwrap();
+        writeln!(temp_file, "denoise = false").unwrap();
+        writeln!(temp_file, "[profiles.office]").unwrap();
+        writeln!(temp_file, "denoise_strength = 0.9").unwrap();
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        config.apply_profile(None).unwrap();
+        assert!(!config.denoise);
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        config.apply_profile(Some("office")).unwrap();
+        assert!(config.denoise);
+        assert_eq!(config.denoise_strength, 0.9);
+        assert_eq!(config.profile.as_deref(), Some("office"));
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        assert!(config.apply_profile(Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_config_host() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "host = \"jack\"").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.host.as_deref(), Some("jack"));
+        assert!(Config::default().host.is_none());
+    }
+
+    #[test]
+    fn test_config_loopback() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "capture_source = \"both\"").unwrap();
+        writeln!(temp_file, "loopback_source = \"speaker.monitor\"").unwrap();
+        writeln!(temp_file, "loopback_label = \"对方\"").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.capture_source, CaptureSource::Both);
+        assert_eq!(config.loopback_source, "speaker.monitor");
+        assert_eq!(config.mic_label, "me");
+        assert_eq!(config.loopback_label, "对方");
+    }
+
+    #[test]
+    fn test_config_sources() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "[[sources]]").unwrap();
+        writeln!(temp_file, "label = \"主持人\"").unwrap();
+        writeln!(temp_file, "device = \"USB Audio A\"").unwrap();
+        writeln!(temp_file, "[[sources]]").unwrap();
+        writeln!(temp_file, "label = \"嘉宾\"").unwrap();
+        writeln!(temp_file, "device_index = 2").unwrap();
+        writeln!(temp_file, "input_channels = [2]").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.sources.len(), 2);
+        assert_eq!(config.sources[0].device.as_deref(), Some("USB Audio A"));
+        assert_eq!(config.sources[1].device_index, Some(2));
+        assert_eq!(config.sources[1].input_channels, Some(vec![2]));
+    }
+
+    #[test]
+    fn test_config_agc() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "agc = true").unwrap();
+        writeln!(temp_file, "agc_target_dbfs = -20.0").unwrap();
+        writeln!(temp_file, "[profiles.laptop]").unwrap();
+        writeln!(temp_file, "agc_max_gain_db = 40.0").unwrap();
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        config.apply_profile(Some("laptop")).unwrap();
+        let params = config.agc_params();
+        assert!(config.agc);
+        assert_eq!(params.target_dbfs, -20.0);
+        assert_eq!(params.max_gain_db, 40.0);
+        assert_eq!(params.attack_ms, AgcParams::default().attack_ms);
+    }
+
+    #[test]
+    fn test_config_vad_adaptive() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "vad_adaptive = true").unwrap();
+        writeln!(temp_file, "vad_margin_db = 12.0").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert!(config.vad_adaptive);
+        assert_eq!(config.vad_margin_db, 12.0);
+        assert!(!Config::default().vad_adaptive);
+        assert_eq!(Config::default().vad_margin_db, 10.0);
+    }
+
+    #[test]
+    fn test_config_save_values_preserves_comments() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "# 模型目录").unwrap();
+        writeln!(temp_file, "model_dir = \"/custom/models\"").unwrap();
+        writeln!(temp_file, "vad_threshold = 0.05").unwrap();
+        writeln!(temp_file, "\n[profiles.quiet]\ndenoise = false").unwrap();
+
+        Config::save_values(temp_file.path(), &[("vad_threshold", 0.002.into())]).unwrap();
+
+        let content = std::fs::read_to_string(temp_file.path()).unwrap();
+        assert!(content.contains("# 模型目录"));
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.vad_threshold, 0.002);
+        assert_eq!(config.model_dir, "/custom/models");
+        assert!(config.profiles.contains_key("quiet"));
+    }
+
+    #[test]
+    fn test_config_save_values_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        Config::save_values(&path, &[("vad_threshold", 0.003.into())]).unwrap();
+        assert_eq!(Config::load(&path).unwrap().vad_threshold, 0.003);
+    }
+
+    #[test]
+    fn test_config_vad_frames() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "vad_frame_ms = 10").unwrap();
+        writeln!(temp_file, "vad_min_speech_ms = 90").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        let params = VadParams::from_config(&config);
+        assert_eq!(params.frame_ms, 10);
+        assert_eq!(params.min_speech_ms, 90);
+        assert_eq!(params.hysteresis_db, VadParams::default().hysteresis_db);
+        assert_eq!(params.hangover_ms, VadParams::default().hangover_ms);
+    }
+
+    #[test]
+    fn test_config_hangover_alias() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "hangover_ms = 250").unwrap();
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.vad_hangover_ms, 250);
+
+        // 新旧名称同时出现时报错，而不是悄悄选择其中一个
+        writeln!(temp_file, "vad_hangover_ms = 100").unwrap();
+        assert!(Config::load(temp_file.path()).is_err());
+    }
+
+    #[test]
+    fn test_config_endpoint_profile() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "endpoint_min_silence_ms = 800").unwrap();
+        writeln!(temp_file, "[profiles.lecture]").unwrap();
+        writeln!(temp_file, "endpoint_min_silence_ms = 1500").unwrap();
+        writeln!(temp_file, "endpoint_max_utterance_ms = 30000").unwrap();
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        let params = EndpointParams::from_config(&config);
+        assert_eq!(params.min_silence_ms, 800);
+        assert_eq!(params.max_utterance_ms, 20000);
+
+        config.apply_profile(Some("lecture")).unwrap();
+        let params = EndpointParams::from_config(&config);
+        assert_eq!(params.min_silence_ms, 1500);
+        assert_eq!(params.min_speech_ms, 500);
+        assert_eq!(params.max_utterance_ms, 30000);
+    }
+
+    #[test]
+    fn test_config_endpoint_strategy() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "endpoint_strategy = \"hybrid\"").unwrap();
+        writeln!(temp_file, "vad_model = \"./models/silero_vad.onnx\"").unwrap();
+        writeln!(temp_file, "[profiles.meeting]").unwrap();
+        writeln!(temp_file, "endpoint_strategy = \"recognizer\"").unwrap();
+
+        let mut config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.endpoint_strategy, EndpointStrategy::Hybrid);
+        assert_eq!(
+            config.vad_model.as_deref(),
+            Some("./models/silero_vad.onnx")
+        );
+        assert_eq!(config.vad_model_threshold, 0.5);
+        assert_eq!(
+            Config::default().endpoint_strategy,
+            EndpointStrategy::Energy
+        );
+
+        config.apply_profile(Some("meeting")).unwrap();
+        assert_eq!(config.endpoint_strategy, EndpointStrategy::Recognizer);
+    }
+
+    #[test]
+    fn test_config_subtitle_defaults() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(temp_file, "subtitle_max_line_width = 32").unwrap();
+
+        let config = Config::load(temp_file.path()).unwrap();
+        assert_eq!(config.subtitle_max_line_width, 32);
+        assert_eq!(config.subtitle_max_lines, 2);
+        assert_eq!(config.subtitle_max_cue_ms, 6000);
+    }
 }
@@ -0,0 +1,81 @@
+use crate::export::{self, TimedWord};
+use crate::segmenter::Utterance;
+
+/// NIST CTM（time-marked conversation）格式
+///
+/// 每个词一行：`<文件> <声道> <开始> <时长> <词> [<置信度>]`，时间以秒为单位。
+/// 声道固定为 1；模型不提供置信度时省略最后一列。词首尾的标点去掉，
+/// 只剩标点的词不输出，便于直接用 SCTK 等工具评测。
+pub fn to_ctm(utterances: &[Utterance], name: &str) -> String {
+    // 文件标识中不能有空白
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect();
+    let mut out = String::new();
+    for word in utterances.iter().flat_map(export::timed_words) {
+        if let Some(line) = ctm_line(&name, &word) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn ctm_line(name: &str, word: &TimedWord) -> Option<String> {
+    let text = word.text.trim_matches(export::is_punctuation);
+    if text.is_empty() {
+        return None;
+    }
+    let mut line = format!(
+        "{} 1 {:.2} {:.2} {}",
+        name,
+        word.start,
+        (word.end - word.start).max(0.0),
+        text
+    );
+    if let Some(confidence) = word.confidence {
+        line.push_str(&format!(" {:.3}", confidence));
+    }
+    Some(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utterance(confidences: &[f32]) -> Utterance {
+        Utterance {
+            text: "你好，世界。".to_string(),
+            tokens: ["你", "好", "，", "世", "界", "。"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            timestamps: vec![0.5, 0.7, 0.8, 1.0, 1.2, 1.3],
+            confidences: confidences.to_vec(),
+            start: 0.4,
+            end: 1.6,
+            ..Utterance::default()
+        }
+    }
+
+    #[test]
+    fn test_ctm_strips_punctuation() {
+        let ctm = to_ctm(&[utterance(&[])], "my talk");
+        assert_eq!(
+            ctm,
+            "my_talk 1 0.50 0.20 你\n\
+             my_talk 1 0.70 0.30 好\n\
+             my_talk 1 1.00 0.20 世\n\
+             my_talk 1 1.20 0.40 界\n"
+        );
+    }
+
+    #[test]
+    fn test_ctm_with_confidence() {
+        let ctm = to_ctm(&[utterance(&[0.9, 0.5, 1.0, 0.8, 0.75, 1.0])], "a");
+        let lines: Vec<&str> = ctm.lines().collect();
+        assert_eq!(lines[0], "a 1 0.50 0.20 你 0.900");
+        assert_eq!(lines[3], "a 1 1.20 0.40 界 0.750");
+    }
+}
@@ -0,0 +1,329 @@
+use std::f32::consts::PI;
+
+/// 分析帧时长（秒），实际帧长取不小于该时长的 2 的幂
+const FRAME_SECONDS: f32 = 0.02;
+/// 噪声估计下降时的平滑系数（跟随得快）
+const NOISE_FALL: f32 = 0.9;
+/// 噪声估计上升时的平滑系数（跟随得慢，避免把语音当成噪声）
+const NOISE_RISE: f32 = 0.999;
+/// 最小值跟踪会系统性低估噪声功率，乘以该系数补偿
+const NOISE_BIAS: f32 = 3.0;
+/// 增益的时间平滑系数，抑制“音乐噪声”
+const GAIN_SMOOTHING: f32 = 0.5;
+
+/// 谱减法降噪器
+///
+/// 使用 50% 重叠的 sqrt-Hann 窗做短时傅里叶变换，按频点跟踪噪声功率的
+/// 最小值（下降快、上升慢），再按过减因子计算每个频点的增益。
+/// 适合抑制空调嗡嗡声、风扇等稳态噪声，对键盘声等瞬态噪声也有一定效果。
+///
+/// 引入一个跳帧（帧长的一半，约 10–20 ms）的延迟。
+pub struct Denoiser {
+    frame_len: usize,
+    hop: usize,
+    window: Vec<f32>,
+    /// 过减因子
+    over_subtraction: f32,
+    /// 最小增益（谱下限）
+    floor: f32,
+    bypass: bool,
+    input: Vec<f32>,
+    overlap: Vec<f32>,
+    noise: Vec<f32>,
+    gain: Vec<f32>,
+    noise_initialized: bool,
+    spectrum: Vec<Complex>,
+}
+
+impl Denoiser {
+    /// # Arguments
+    /// * `sample_rate` - 输入采样率
+    /// * `strength` - 降噪强度，0.0（不处理）到 1.0（最强）
+    pub fn new(sample_rate: u32, strength: f32) -> Self {
+        let frame_len = ((sample_rate as f32 * FRAME_SECONDS) as usize)
+            .max(64)
+            .next_power_of_two();
+        let hop = frame_len / 2;
+        let window = (0..frame_len)
+            .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f32 / frame_len as f32).cos()).sqrt())
+            .collect();
+        let bins = frame_len / 2 + 1;
+
+        let mut denoiser = Self {
+            frame_len,
+            hop,
+            window,
+            over_subtraction: 1.0,
+            floor: 1.0,
+            bypass: false,
+            // 预填充半帧零样本，使第一帧输出也能完整重建
+            input: vec![0.0; hop],
+            overlap: vec![0.0; frame_len],
+            noise: vec![0.0; bins],
+            gain: vec![1.0; bins],
+            noise_initialized: false,
+            spectrum: vec![Complex::default(); frame_len],
+        };
+        denoiser.set_strength(strength);
+        denoiser
+    }
+
+    /// 设置降噪强度，0.0 到 1.0
+    pub fn set_strength(&mut self, strength: f32) {
+        let strength = strength.clamp(0.0, 1.0);
+        self.over_subtraction = 1.0 + strength;
+        // 强度为 1 时最多衰减 20 dB
+        self.floor = 1.0 - 0.9 * strength;
+    }
+
+    /// 旁路开关：开启后输出原始音频，便于对比降噪效果
+    ///
+    /// 旁路时仍按帧重叠相加，只是不做谱减，输出与降噪时有相同的延迟，
+    /// 运行中切换不会造成时间跳变，也不会重放切换前缓存的数据。
+    pub fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass
+    }
+
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        self.input.extend_from_slice(samples);
+        let mut output = Vec::with_capacity(samples.len() + self.hop);
+
+        while self.input.len() >= self.frame_len {
+            if self.bypass {
+                self.pass_frame();
+            } else {
+                self.process_frame();
+            }
+            output.extend_from_slice(&self.overlap[..self.hop]);
+            self.overlap.copy_within(self.hop.., 0);
+            let tail = self.frame_len - self.hop;
+            self.overlap[tail..].iter_mut().for_each(|s| *s = 0.0);
+            self.input.drain(..self.hop);
+        }
+
+        output
+    }
+
+    /// 旁路：不经频域处理，直接按分析窗和合成窗重叠相加（重建结果即原始音频）
+    fn pass_frame(&mut self) {
+        for i in 0..self.frame_len {
+            self.overlap[i] += self.input[i] * self.window[i] * self.window[i];
+        }
+    }
+
+    fn process_frame(&mut self) {
+        for (i, slot) in self.spectrum.iter_mut().enumerate() {
+            *slot = Complex::new(self.input[i] * self.window[i], 0.0);
+        }
+        fft(&mut self.spectrum, false);
+
+        let bins = self.frame_len / 2 + 1;
+        for k in 0..bins {
+            let power = self.spectrum[k].norm_sqr();
+
+            if !self.noise_initialized {
+                self.noise[k] = power;
+            } else if power < self.noise[k] {
+                self.noise[k] = NOISE_FALL * self.noise[k] + (1.0 - NOISE_FALL) * power;
+            } else {
+                self.noise[k] = NOISE_RISE * self.noise[k] + (1.0 - NOISE_RISE) * power;
+            }
+
+            let target = if power > f32::EPSILON {
+                (1.0 - self.over_subtraction * NOISE_BIAS * self.noise[k] / power)
+                    .max(self.floor * self.floor)
+                    .sqrt()
+            } else {
+                self.floor
+            };
+            self.gain[k] = GAIN_SMOOTHING * self.gain[k] + (1.0 - GAIN_SMOOTHING) * target;
+        }
+        self.noise_initialized = true;
+
+        // 实信号频谱共轭对称
+        for k in 0..bins {
+            let g = self.gain[k];
+            self.spectrum[k] = self.spectrum[k].scale(g);
+            if k > 0 && k < self.frame_len - k {
+                let mirror = self.frame_len - k;
+                self.spectrum[mirror] = self.spectrum[mirror].scale(g);
+            }
+        }
+
+        fft(&mut self.spectrum, true);
+        let norm = 1.0 / self.frame_len as f32;
+        for i in 0..self.frame_len {
+            self.overlap[i] += self.spectrum[i].re * norm * self.window[i];
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    fn scale(self, k: f32) -> Self {
+        Self::new(self.re * k, self.im * k)
+    }
+}
+
+/// 原地基 2 FFT，`data.len()` 必须是 2 的幂；`inverse` 时不做 1/N 归一化
+fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * PI / len as f32;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+        for start in (0..n).step_by(len) {
+            let (mut cur_re, mut cur_im) = (1.0f32, 0.0f32);
+            for k in 0..len / 2 {
+                let a = data[start + k];
+                let b = data[start + k + len / 2];
+                let t = Complex::new(b.re * cur_re - b.im * cur_im, b.re * cur_im + b.im * cur_re);
+                data[start + k] = Complex::new(a.re + t.re, a.im + t.im);
+                data[start + k + len / 2] = Complex::new(a.re - t.re, a.im - t.im);
+                let next_re = cur_re * w_re - cur_im * w_im;
+                cur_im = cur_re * w_im + cur_im * w_re;
+                cur_re = next_re;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    /// 确定性的伪随机白噪声
+    fn noise(len: usize, amplitude: f32) -> Vec<f32> {
+        let mut state = 0x1234_5678u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft_roundtrip() {
+        let original: Vec<Complex> = (0..16).map(|i| Complex::new(i as f32, 0.0)).collect();
+        let mut data = original.clone();
+        fft(&mut data, false);
+        fft(&mut data, true);
+        for (a, b) in data.iter().zip(&original) {
+            assert!((a.re / 16.0 - b.re).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_bypass_is_delayed_passthrough() {
+        let mut denoiser = Denoiser::new(16000, 1.0);
+        denoiser.set_bypass(true);
+        let input = noise(1000, 0.1);
+        let output = denoiser.process(&input);
+
+        let delay = denoiser.hop;
+        assert_eq!(output.len() + denoiser.input.len(), input.len() + delay);
+        for i in delay..output.len() {
+            assert!((output[i] - input[i - delay]).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_toggling_bypass_keeps_timing() {
+        let mut denoiser = Denoiser::new(16000, 0.0);
+        let input: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+
+        let mut output = Vec::new();
+        for (i, block) in input.chunks(1600).enumerate() {
+            denoiser.set_bypass(i % 2 == 1);
+            output.extend(denoiser.process(block));
+        }
+
+        let delay = denoiser.hop;
+        assert_eq!(output.len() + denoiser.input.len(), input.len() + delay);
+        for i in delay..output.len() {
+            assert!((output[i] - input[i - delay]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_zero_strength_reconstructs_input() {
+        let mut denoiser = Denoiser::new(16000, 0.0);
+        let input: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let output = denoiser.process(&input);
+
+        let delay = denoiser.hop;
+        assert_eq!(output.len() + denoiser.input.len(), input.len() + delay);
+        for i in delay..output.len() {
+            assert!((output[i] - input[i - delay]).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_suppresses_stationary_noise() {
+        let mut denoiser = Denoiser::new(16000, 1.0);
+        let input = noise(32000, 0.05);
+        let output = denoiser.process(&input);
+
+        let settled = &output[16000..];
+        // 收敛后至少衰减 6 dB
+        assert!(rms(settled) < rms(&input[16000..]) * 0.5);
+    }
+
+    #[test]
+    fn test_preserves_speech_over_noise() {
+        let mut denoiser = Denoiser::new(16000, 0.7);
+        let mut input = noise(16000, 0.02);
+        // 先给 1 秒纯噪声建立噪声估计，再叠加 0.5 幅度的“语音”
+        let background = noise(16000, 0.02);
+        input.extend(
+            background
+                .iter()
+                .enumerate()
+                .map(|(i, n)| n + (i as f32 * 0.1).sin() * 0.5),
+        );
+
+        let output = denoiser.process(&input);
+        let voiced = &output[20000..30000];
+        assert!(rms(voiced) > 0.3);
+    }
+}
@@ -0,0 +1,407 @@
+use crate::audio::{AudioCapture, CaptureOptions, TARGET_SAMPLE_RATE};
+use crate::config::Config;
+use crate::preprocess::Preprocessor;
+use crate::resampler::Resampler;
+use crate::vad::FrameVad;
+use anyhow::Result;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// 分析帧长（秒），与实时采集回调的典型块长相当
+const FRAME_SECONDS: f32 = 0.03;
+/// 样本绝对值达到该值视为削波（混音后已限制在 [-1, 1]）
+const CLIP_LEVEL: f32 = 0.999;
+/// 噪声底 / 语音电平分别取帧能量的这两个分位数
+const NOISE_PERCENTILE: f32 = 0.1;
+const SPEECH_PERCENTILE: f32 = 0.9;
+/// 校准时取帧能量中位数作为环境噪声底，对偶发的咳嗽、敲击声不敏感
+const CALIBRATION_PERCENTILE: f32 = 0.5;
+/// 能量下限，避免数字静音时出现 -inf dB
+const MIN_ENERGY: f32 = 1e-10;
+
+/// 麦克风测试结果
+#[derive(Debug, Clone)]
+pub struct MicReport {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: String,
+    pub seconds: f32,
+    /// 混音后原始音频的 RMS（dBFS）
+    pub rms_dbfs: f32,
+    /// 混音后原始音频的峰值（dBFS）
+    pub peak_dbfs: f32,
+    /// 削波样本占比（%）
+    pub clipping_percent: f32,
+    /// 经预处理、重采样和 AGC 后（VAD 实际看到的音频）的帧能量统计
+    pub levels: FrameLevels,
+}
+
+/// 帧能量（均方值，与 `vad_threshold` 同一单位）的统计
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameLevels {
+    pub noise_floor: f32,
+    pub speech_level: f32,
+}
+
+/// 按固定帧长计算每帧均方能量，按升序排列
+fn sorted_frame_energies(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let frame = ((sample_rate as f32 * FRAME_SECONDS) as usize).max(1);
+    let mut energies: Vec<f32> = samples
+        .chunks_exact(frame)
+        .map(|chunk| chunk.iter().map(|s| s * s).sum::<f32>() / chunk.len() as f32)
+        .collect();
+    energies.sort_by(f32::total_cmp);
+    energies
+}
+
+/// 取已排序数据的分位数，空数据返回 0
+fn percentile(sorted: &[f32], p: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted[((sorted.len() - 1) as f32 * p).round() as usize]
+}
+
+impl FrameLevels {
+    /// 取帧能量的低 / 高分位数作为噪声底和语音电平
+    pub fn measure(samples: &[f32], sample_rate: u32) -> Self {
+        let energies = sorted_frame_energies(samples, sample_rate);
+        Self {
+            noise_floor: percentile(&energies, NOISE_PERCENTILE),
+            speech_level: percentile(&energies, SPEECH_PERCENTILE),
+        }
+    }
+
+    /// 估计信噪比（dB）
+    pub fn snr_db(&self) -> f32 {
+        10.0 * (self.speech_level.max(MIN_ENERGY) / self.noise_floor.max(MIN_ENERGY)).log10()
+    }
+
+    /// 建议的 `vad_threshold`
+    ///
+    /// 取噪声底和语音电平的几何平均（对数刻度上的中点），
+    /// 并保证至少比噪声底高 6 dB；没有测到语音时取噪声底的 4 倍。
+    pub fn suggested_threshold(&self) -> f32 {
+        let noise = self.noise_floor.max(MIN_ENERGY);
+        let floor = noise * 4.0;
+        if self.speech_level <= floor {
+            return floor;
+        }
+        (noise * self.speech_level).sqrt().max(floor)
+    }
+}
+
+impl MicReport {
+    /// 根据测试结果给出的问题提示
+    pub fn warnings(&self) -> Vec<&'static str> {
+        let mut warnings = Vec::new();
+        if self.peak_dbfs < -30.0 {
+            warnings.push("输入电平过低：请调高系统输入音量或靠近麦克风，也可以启用 agc");
+        }
+        if self.clipping_percent > 0.1 {
+            warnings.push("存在削波：请调低系统输入音量");
+        }
+        if self.levels.snr_db() < 10.0 {
+            warnings.push("信噪比过低：测试期间可能没有说话，或环境噪声较大（可尝试 denoise）");
+        }
+        warnings
+    }
+}
+
+/// 环境噪声校准结果
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    pub device: String,
+    pub seconds: f32,
+    /// 环境噪声底（帧能量中位数）
+    pub noise_floor: f32,
+    /// 环境声音中较响的部分（帧能量 90% 分位数）
+    pub noise_peak: f32,
+    pub margin_db: f32,
+    /// 噪声底加余量后的 `vad_threshold`
+    pub threshold: f32,
+}
+
+impl Calibration {
+    /// 根据一段环境噪声（VAD 所见的 16 kHz 音频）计算阈值
+    pub fn from_samples(samples: &[f32], sample_rate: u32, margin_db: f32) -> Self {
+        let energies = sorted_frame_energies(samples, sample_rate);
+        let noise_floor = percentile(&energies, CALIBRATION_PERCENTILE);
+        Self {
+            device: String::new(),
+            seconds: samples.len() as f32 / sample_rate as f32,
+            noise_floor,
+            noise_peak: percentile(&energies, SPEECH_PERCENTILE),
+            margin_db,
+            threshold: noise_floor.max(MIN_ENERGY) * 10f32.powf(margin_db / 10.0),
+        }
+    }
+
+    /// 根据校准结果给出的问题提示
+    pub fn warnings(&self) -> Vec<&'static str> {
+        let mut warnings = Vec::new();
+        if self.noise_peak > self.threshold {
+            warnings.push(
+                "校准期间有明显高于噪声底的声音（说话或突发噪声），建议保持安静后重新校准，或增大 --margin-db",
+            );
+        }
+        if self.noise_floor < MIN_ENERGY {
+            warnings.push("几乎没有收到声音：设备可能已静音，阈值可能过低");
+        }
+        warnings
+    }
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "设备:       {}", self.device)?;
+        writeln!(f, "时长:       {:.1} 秒", self.seconds)?;
+        writeln!(
+            f,
+            "噪声底:     {:.6}（{:.1} dBFS）",
+            self.noise_floor,
+            10.0 * self.noise_floor.max(MIN_ENERGY).log10()
+        )?;
+        writeln!(f, "余量:       {:.1} dB", self.margin_db)?;
+        write!(f, "vad_threshold = {:.6}", self.threshold)
+    }
+}
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-5).log10()
+}
+
+/// 分析混音后的原始音频（采集采样率）和 VAD 所见的音频（16 kHz）
+pub fn analyze(raw: &[f32], processed: &[f32]) -> (f32, f32, f32, FrameLevels) {
+    let (sum, peak, clipped) =
+        raw.iter()
+            .fold((0.0f64, 0.0f32, 0usize), |(sum, peak, clipped), &s| {
+                let abs = s.abs();
+                (
+                    sum + (s as f64) * (s as f64),
+                    peak.max(abs),
+                    clipped + (abs >= CLIP_LEVEL) as usize,
+                )
+            });
+    let len = raw.len().max(1);
+    let rms = (sum / len as f64).sqrt() as f32;
+
+    (
+        to_dbfs(rms),
+        to_dbfs(peak),
+        clipped as f32 * 100.0 / len as f32,
+        FrameLevels::measure(processed, TARGET_SAMPLE_RATE),
+    )
+}
+
+/// 一段测试录音
+struct Recording {
+    capture: AudioCapture,
+    /// 混音后的原始音频（采集采样率）
+    raw: Vec<f32>,
+    /// 经预处理、重采样和 AGC 后的 16 kHz 音频，与 VAD 看到的信号一致
+    processed: Vec<f32>,
+}
+
+/// 从所选设备录制 `duration`，经过与实时识别相同的混音、预处理、重采样和 AGC
+fn record(config: &Config, options: CaptureOptions, duration: Duration) -> Result<Recording> {
+    let mut capture = AudioCapture::open(options)?;
+    let sample_rate = capture.config().sample_rate();
+    let mut preprocessor = Preprocessor::new(config, sample_rate);
+    let mut resampler = Resampler::new_if_needed(config.resampler, sample_rate, TARGET_SAMPLE_RATE);
+    // 只用于驱动 AGC 的增益调整，与实时识别一致
+    let mut vad = FrameVad::from_config(config, TARGET_SAMPLE_RATE);
+
+    let mut raw = Vec::new();
+    let mut processed = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let Some(samples) = capture.try_recv() else {
+            std::thread::sleep(Duration::from_millis(10));
+            continue;
+        };
+        raw.extend_from_slice(&samples);
+        let samples = preprocessor.process(samples);
+        let mut samples = match resampler {
+            Some(ref mut r) => r.resample(&samples),
+            None => samples,
+        };
+        preprocessor.apply_gain(&mut samples, vad.is_speech());
+        vad.process(&samples, |_, _| {});
+        processed.extend(samples);
+    }
+
+    if raw.is_empty() {
+        anyhow::bail!("测试期间没有收到任何音频数据，请检查设备是否被占用或已静音");
+    }
+    Ok(Recording {
+        capture,
+        raw,
+        processed,
+    })
+}
+
+/// 录音并报告输入电平、削波和信噪比
+pub fn run_mic_test(
+    config: &Config,
+    options: CaptureOptions,
+    duration: Duration,
+) -> Result<MicReport> {
+    let Recording {
+        capture,
+        raw,
+        processed,
+    } = record(config, options, duration)?;
+    let sample_rate = capture.config().sample_rate();
+
+    let (rms_dbfs, peak_dbfs, clipping_percent, levels) = analyze(&raw, &processed);
+    let capture_config = capture.config();
+    Ok(MicReport {
+        device: capture.device_name().to_string(),
+        sample_rate,
+        channels: capture_config.channels(),
+        sample_format: format!("{:?}", capture_config.sample_format),
+        seconds: raw.len() as f32 / sample_rate as f32,
+        rms_dbfs,
+        peak_dbfs,
+        clipping_percent,
+        levels,
+    })
+}
+
+/// 录制一段环境噪声，按噪声底加 `margin_db` 计算 VAD 阈值
+///
+/// 在 VAD 的输入上测量（AGC 之后）：VAD 判为静音时 AGC 保持增益，
+/// 环境噪声不会被放大，得到的阈值与实时识别时 VAD 看到的电平一致。
+pub fn run_calibration(
+    config: &Config,
+    options: CaptureOptions,
+    duration: Duration,
+    margin_db: f32,
+) -> Result<Calibration> {
+    let recording = record(config, options, duration)?;
+    Ok(Calibration {
+        device: recording.capture.device_name().to_string(),
+        ..Calibration::from_samples(&recording.processed, TARGET_SAMPLE_RATE, margin_db)
+    })
+}
+
+impl fmt::Display for MicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "设备:       {}", self.device)?;
+        writeln!(
+            f,
+            "格式:       {} Hz, {} 声道, {}",
+            self.sample_rate, self.channels, self.sample_format
+        )?;
+        writeln!(f, "时长:       {:.1} 秒", self.seconds)?;
+        writeln!(f, "RMS:        {:.1} dBFS", self.rms_dbfs)?;
+        writeln!(f, "峰值:       {:.1} dBFS", self.peak_dbfs)?;
+        writeln!(f, "削波:       {:.2}%", self.clipping_percent)?;
+        writeln!(
+            f,
+            "噪声底:     {:.6}（{:.1} dBFS）",
+            self.levels.noise_floor,
+            10.0 * self.levels.noise_floor.max(MIN_ENERGY).log10()
+        )?;
+        writeln!(
+            f,
+            "语音电平:   {:.6}（{:.1} dBFS）",
+            self.levels.speech_level,
+            10.0 * self.levels.speech_level.max(MIN_ENERGY).log10()
+        )?;
+        writeln!(f, "估计信噪比: {:.1} dB", self.levels.snr_db())?;
+        write!(
+            f,
+            "建议 vad_threshold = {:.6}",
+            self.levels.suggested_threshold()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 秒静音底噪 + 1 秒“语音”
+    fn noise_then_speech() -> Vec<f32> {
+        let mut samples: Vec<f32> = (0..16000).map(|i| (i as f32 * 1.7).sin() * 0.001).collect();
+        samples.extend((0..16000).map(|i| (i as f32 * 0.05).sin() * 0.3));
+        samples
+    }
+
+    #[test]
+    fn test_frame_levels() {
+        let levels = FrameLevels::measure(&noise_then_speech(), 16000);
+        assert!(levels.noise_floor < 1e-5);
+        assert!((levels.speech_level - 0.045).abs() < 0.005);
+        assert!(levels.snr_db() > 30.0);
+    }
+
+    #[test]
+    fn test_suggested_threshold_between_noise_and_speech() {
+        let levels = FrameLevels::measure(&noise_then_speech(), 16000);
+        let threshold = levels.suggested_threshold();
+        assert!(threshold > levels.noise_floor * 4.0);
+        assert!(threshold < levels.speech_level);
+    }
+
+    #[test]
+    fn test_suggested_threshold_without_speech() {
+        let levels = FrameLevels {
+            noise_floor: 0.001,
+            speech_level: 0.0012,
+        };
+        assert!((levels.suggested_threshold() - 0.004).abs() < 1e-7);
+    }
+
+    #[test]
+    fn test_analyze_peak_and_clipping() {
+        let mut raw = vec![0.5; 990];
+        raw.extend(vec![1.0; 10]);
+        let (rms, peak, clipping, _) = analyze(&raw, &[]);
+        assert!((peak - 0.0).abs() < 1e-3);
+        assert!((clipping - 1.0).abs() < 1e-3);
+        assert!(rms < 0.0 && rms > -7.0);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let report = MicReport {
+            device: "mic".to_string(),
+            sample_rate: 48000,
+            channels: 1,
+            sample_format: "I16".to_string(),
+            seconds: 5.0,
+            rms_dbfs: -60.0,
+            peak_dbfs: -45.0,
+            clipping_percent: 0.0,
+            levels: FrameLevels {
+                noise_floor: 1e-7,
+                speech_level: 2e-7,
+            },
+        };
+        let warnings = report.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(report.to_string().contains("建议 vad_threshold"));
+    }
+
+    #[test]
+    fn test_calibration_threshold_above_noise_floor() {
+        let noise: Vec<f32> = (0..48000).map(|i| (i as f32 * 1.7).sin() * 0.01).collect();
+        let calibration = Calibration::from_samples(&noise, 16000, 10.0);
+        assert!((calibration.noise_floor - 5e-5).abs() < 5e-6);
+        assert!((calibration.threshold / calibration.noise_floor - 10.0).abs() < 1e-3);
+        assert!(calibration.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_calibration_warns_about_speech() {
+        let mut samples: Vec<f32> = (0..48000).map(|i| (i as f32 * 1.7).sin() * 0.001).collect();
+        samples.extend((0..16000).map(|i| (i as f32 * 0.05).sin() * 0.3));
+        let calibration = Calibration::from_samples(&samples, 16000, 10.0);
+        assert_eq!(calibration.warnings().len(), 1);
+        assert!(calibration.to_string().contains("vad_threshold"));
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+/// 多声道合并为单声道的方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelMode {
+    /// 对所选声道取平均（默认）
+    #[default]
+    Average,
+    /// 每个数据块自动选取所选声道中能量最高的一个
+    Loudest,
+}
+
+/// 声道选择与混音
+///
+/// 先按 `input_channels`（从 1 开始编号）挑选声道，为空时使用全部声道；
+/// 再按 [`ChannelMode`] 合并为单声道。输出始终限制在 [-1.0, 1.0]，
+/// 防止后续处理阶段出现削波。
+#[derive(Debug, Clone)]
+pub struct Downmixer {
+    channels: usize,
+    selected: Vec<usize>,
+    mode: ChannelMode,
+}
+
+impl Downmixer {
+    /// # Arguments
+    /// * `channels` - 设备声道数
+    /// * `selection` - 要使用的声道（从 1 开始），为空表示全部
+    /// * `mode` - 合并方式
+    pub fn new(channels: u16, selection: &[u16], mode: ChannelMode) -> anyhow::Result<Self> {
+        let channels = channels.max(1) as usize;
+        let selected = if selection.is_empty() {
+            (0..channels).collect()
+        } else {
+            let mut selected = Vec::with_capacity(selection.len());
+            for &ch in selection {
+                if ch == 0 || ch as usize > channels {
+                    anyhow::bail!("声道 {} 超出范围，设备共有 {} 个声道", ch, channels);
+                }
+                if !selected.contains(&(ch as usize - 1)) {
+                    selected.push(ch as usize - 1);
+                }
+            }
+            selected
+        };
+
+        Ok(Self {
+            channels,
+            selected,
+            mode,
+        })
+    }
+
+    /// 将交错排列的多声道样本合并为单声道，结果写入 `out`（会先清空）
+    pub fn process(&self, interleaved: &[f32], out: &mut Vec<f32>) {
+        out.clear();
+
+        if self.channels == 1 {
+            out.extend(interleaved.iter().map(|s| s.clamp(-1.0, 1.0)));
+            return;
+        }
+
+        let frames = interleaved.chunks_exact(self.channels);
+        match self.mode {
+            ChannelMode::Average => {
+                let scale = 1.0 / self.selected.len() as f32;
+                out.extend(frames.map(|frame| {
+                    let sum: f32 = self.selected.iter().map(|&ch| frame[ch]).sum();
+                    (sum * scale).clamp(-1.0, 1.0)
+                }));
+            }
+            ChannelMode::Loudest => {
+                let loudest = self.loudest_channel(interleaved);
+                out.extend(frames.map(|frame| frame[loudest].clamp(-1.0, 1.0)));
+            }
+        }
+    }
+
+    /// 所选声道中能量最高的声道索引
+    fn loudest_channel(&self, interleaved: &[f32]) -> usize {
+        let mut best = self.selected[0];
+        let mut best_energy = f32::MIN;
+        for &ch in &self.selected {
+            let energy: f32 = interleaved
+                .chunks_exact(self.channels)
+                .map(|frame| frame[ch] * frame[ch])
+                .sum();
+            if energy > best_energy {
+                best_energy = energy;
+                best = ch;
+            }
+        }
+        best
+    }
+
+    /// 所需的最少设备声道数
+    pub fn required_channels(selection: &[u16]) -> u16 {
+        selection.iter().copied().max().unwrap_or(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mono_passthrough() {
+        let mixer = Downmixer::new(1, &[], ChannelMode::Average).unwrap();
+        let mut out = Vec::new();
+        mixer.process(&[0.1, -0.2, 0.3], &mut out);
+        assert_eq!(out, vec![0.1, -0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_average_does_not_clip_correlated_channels() {
+        let mixer = Downmixer::new(2, &[], ChannelMode::Average).unwrap();
+        let mut out = Vec::new();
+        mixer.process(&[0.9, 0.9, -0.8, -0.8], &mut out);
+        assert!((out[0] - 0.9).abs() < 1e-6);
+        assert!((out[1] + 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_select_single_channel() {
+        let mixer = Downmixer::new(2, &[2], ChannelMode::Average).unwrap();
+        let mut out = Vec::new();
+        mixer.process(&[0.5, 0.1, 0.5, 0.2], &mut out);
+        assert_eq!(out, vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_select_out_of_range() {
+        assert!(Downmixer::new(2, &[3], ChannelMode::Average).is_err());
+        assert!(Downmixer::new(2, &[0], ChannelMode::Average).is_err());
+    }
+
+    #[test]
+    fn test_loudest_channel() {
+        let mixer = Downmixer::new(3, &[], ChannelMode::Loudest).unwrap();
+        let mut out = Vec::new();
+        // 第 2 声道能量最高
+        mixer.process(&[0.01, 0.5, 0.02, -0.01, -0.4, 0.0], &mut out);
+        assert_eq!(out, vec![0.5, -0.4]);
+    }
+
+    #[test]
+    fn test_loudest_respects_selection() {
+        let mixer = Downmixer::new(3, &[1, 3], ChannelMode::Loudest).unwrap();
+        let mut out = Vec::new();
+        mixer.process(&[0.01, 0.9, 0.2, 0.01, 0.9, 0.3], &mut out);
+        assert_eq!(out, vec![0.2, 0.3]);
+    }
+
+    #[test]
+    fn test_output_is_clamped() {
+        let mixer = Downmixer::new(1, &[], ChannelMode::Average).unwrap();
+        let mut out = Vec::new();
+        mixer.process(&[1.5, -2.0], &mut out);
+        assert_eq!(out, vec![1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_required_channels() {
+        assert_eq!(Downmixer::required_channels(&[]), 1);
+        assert_eq!(Downmixer::required_channels(&[1, 4]), 4);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::config::Config;
+use crate::ffi::{EndpointRules, VoiceActivityDetector};
+use crate::resampler::{Resampler, ResamplerKind};
+use crate::vad::{EndpointDetector, EndpointParams, VadStats};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 规则 3 关闭时使用的语句长度（秒），实际上永远不会触发
+const RULE3_DISABLED_SECONDS: f32 = 1.0e6;
+
+/// 断句策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointStrategy {
+    /// 能量 VAD：语音后静音达到 endpoint_min_silence_ms
+    #[default]
+    Energy,
+    /// sherpa-onnx 识别器内置规则（根据解码结果中的尾部静音判断）
+    Recognizer,
+    /// silero 神经网络 VAD 判定语音段结束（需要 vad_model）
+    Neural,
+    /// 识别器规则和 VAD（设置了 vad_model 时为神经网络 VAD，否则为能量 VAD）同时满足
+    Hybrid,
+}
+
+impl EndpointStrategy {
+    /// 是否需要识别器的断句规则
+    pub fn uses_recognizer(self) -> bool {
+        matches!(self, Self::Recognizer | Self::Hybrid)
+    }
+}
+
+impl FromStr for EndpointStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "energy" => Ok(Self::Energy),
+            "recognizer" => Ok(Self::Recognizer),
+            "neural" => Ok(Self::Neural),
+            "hybrid" => Ok(Self::Hybrid),
+            _ => anyhow::bail!(
+                "未知的断句策略: {}（可选 energy、recognizer、neural、hybrid）",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for EndpointStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Energy => "energy",
+            Self::Recognizer => "recognizer",
+            Self::Neural => "neural",
+            Self::Hybrid => "hybrid",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 断句原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndpointReason {
+    /// 能量 VAD：语音后的静音达到阈值
+    Silence {
+        speech_seconds: f32,
+        silence_seconds: f32,
+    },
+    /// 语句达到最长时长，强制断句
+    MaxUtterance { seconds: f32 },
+    /// 识别器内置规则
+    Recognizer,
+    /// 神经网络 VAD 判定语音段结束
+    NeuralVad,
+    /// 识别器规则和 VAD 同时满足
+    Hybrid { vad: Box<EndpointReason> },
+}
+
+impl fmt::Display for EndpointReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Silence {
+                speech_seconds,
+                silence_seconds,
+            } => write!(
+                f,
+                "能量 VAD：语音 {:.2} 秒后静音 {:.2} 秒",
+                speech_seconds, silence_seconds
+            ),
+            Self::MaxUtterance { seconds } => {
+                write!(f, "达到最长语句时长（{:.1} 秒），强制断句", seconds)
+            }
+            Self::Recognizer => write!(f, "识别器规则"),
+            Self::NeuralVad => write!(f, "神经网络 VAD：语音段结束"),
+            Self::Hybrid { vad } => write!(f, "识别器规则 + {}", vad),
+        }
+    }
+}
+
+/// 根据断句参数生成识别器内置规则
+///
+/// 规则 2 对应 `endpoint_min_silence_ms`；规则 1（未识别出文字）取其两倍，
+/// 避免背景噪声被频繁断开；规则 3 对应 `endpoint_max_utterance_ms`。
+pub fn recognizer_rules(config: &Config) -> EndpointRules {
+    let params = EndpointParams::from_config(config);
+    let min_silence = params.min_silence_ms as f32 / 1000.0;
+    EndpointRules {
+        rule1_min_trailing_silence: min_silence * 2.0,
+        rule2_min_trailing_silence: min_silence,
+        rule3_min_utterance_length: if params.max_utterance_ms > 0 {
+            params.max_utterance_ms as f32 / 1000.0
+        } else {
+            RULE3_DISABLED_SECONDS
+        },
+    }
+}
+
+/// 神经网络 VAD，输入采样率不是 16 kHz 时先重采样
+struct NeuralVad {
+    vad: VoiceActivityDetector,
+    resampler: Option<Resampler>,
+    segment_ended: bool,
+}
+
+impl NeuralVad {
+    fn from_config(config: &Config, sample_rate: u32) -> Result<Self> {
+        let model = config
+            .vad_model
+            .as_deref()
+            .context("神经网络 VAD 需要设置 vad_model（silero_vad.onnx 的路径）")?;
+        let params = EndpointParams::from_config(config);
+        let vad = VoiceActivityDetector::new(
+            model,
+            config.vad_model_threshold,
+            params.min_silence_ms as f32 / 1000.0,
+            params.min_speech_ms as f32 / 1000.0,
+            // 强制断句由能量检测器的最长语句时长负责
+            RULE3_DISABLED_SECONDS,
+        )?;
+        Ok(Self {
+            vad,
+            resampler: Resampler::new_if_needed(
+                ResamplerKind::Linear,
+                sample_rate,
+                VoiceActivityDetector::SAMPLE_RATE,
+            ),
+            segment_ended: false,
+        })
+    }
+
+    fn accept_waveform(&mut self, samples: &[f32]) {
+        match self.resampler {
+            Some(ref mut r) => self.vad.accept_waveform(&r.resample(samples)),
+            None => self.vad.accept_waveform(samples),
+        }
+        if self.vad.pop_segments() > 0 {
+            self.segment_ended = true;
+        }
+    }
+}
+
+/// 按所选策略判断断句
+///
+/// 能量检测器始终运行：它提供语句统计（录音元数据），并负责最长语句时长的强制断句。
+pub struct Endpointer {
+    strategy: EndpointStrategy,
+    energy: EndpointDetector,
+    neural: Option<NeuralVad>,
+    log_name: Option<String>,
+    waiting_logged: bool,
+}
+
+impl Endpointer {
+    pub fn new(strategy: EndpointStrategy, energy: EndpointDetector) -> Self {
+        Self {
+            strategy,
+            energy,
+            neural: None,
+            log_name: None,
+            waiting_logged: false,
+        }
+    }
+
+    /// 按配置创建；`neural` 策略或设置了 `vad_model` 的 `hybrid` 策略会加载神经网络 VAD
+    pub fn from_config(config: &Config, sample_rate: u32) -> Result<Self> {
+        let strategy = config.endpoint_strategy;
+        let mut endpointer =
+            Self::new(strategy, EndpointDetector::from_config(config, sample_rate));
+        let wants_neural = strategy == EndpointStrategy::Neural
+            || (strategy == EndpointStrategy::Hybrid && config.vad_model.is_some());
+        if wants_neural {
+            endpointer.neural = Some(NeuralVad::from_config(config, sample_rate)?);
+        }
+        Ok(endpointer)
+    }
+
+    /// 将每次断句判定及原因输出到标准错误，`name` 用于区分多路来源
+    pub fn enable_log(&mut self, name: &str) {
+        self.log_name = Some(name.to_string());
+    }
+
+    pub fn strategy(&self) -> EndpointStrategy {
+        self.strategy
+    }
+
+    pub fn accept_waveform(&mut self, samples: &[f32]) {
+        self.energy.accept_waveform(samples);
+        if let Some(ref mut neural) = self.neural {
+            neural.accept_waveform(samples);
+        }
+    }
+
+    /// 判断是否断句；`recognizer_endpoint` 只在策略需要时调用
+    pub fn check(&mut self, recognizer_endpoint: impl FnOnce() -> bool) -> Option<EndpointReason> {
+        let reason = self.decide(recognizer_endpoint);
+        if let (Some(name), Some(reason)) = (&self.log_name, &reason) {
+            eprintln!("[DEBUG] {}: 断句（{}）：{}", name, self.strategy, reason);
+        }
+        reason
+    }
+
+    fn decide(&mut self, recognizer_endpoint: impl FnOnce() -> bool) -> Option<EndpointReason> {
+        if self.energy.is_forced_cut() {
+            return Some(EndpointReason::MaxUtterance {
+                seconds: self.energy.utterance_seconds(),
+            });
+        }
+
+        match self.strategy {
+            EndpointStrategy::Energy => self.energy_reason(),
+            EndpointStrategy::Recognizer => {
+                recognizer_endpoint().then_some(EndpointReason::Recognizer)
+            }
+            EndpointStrategy::Neural => self.neural_reason(),
+            EndpointStrategy::Hybrid => {
+                let vad = if self.neural.is_some() {
+                    self.neural_reason()
+                } else {
+                    self.energy_reason()
+                }?;
+                if recognizer_endpoint() {
+                    return Some(EndpointReason::Hybrid { vad: Box::new(vad) });
+                }
+                if !self.waiting_logged {
+                    self.waiting_logged = true;
+                    if let Some(name) = &self.log_name {
+                        eprintln!("[DEBUG] {}: {}，等待识别器规则确认", name, vad);
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    fn energy_reason(&self) -> Option<EndpointReason> {
+        self.energy.is_endpoint().then(|| {
+            let stats = self.energy.stats();
+            EndpointReason::Silence {
+                speech_seconds: stats.speech_seconds,
+                silence_seconds: stats.trailing_silence_seconds,
+            }
+        })
+    }
+
+    fn neural_reason(&self) -> Option<EndpointReason> {
+        self.neural
+            .as_ref()
+            .filter(|n| n.segment_ended)
+            .map(|_| EndpointReason::NeuralVad)
+    }
+
+    /// 当前语句的能量 VAD 统计
+    pub fn stats(&self) -> VadStats {
+        self.energy.stats()
+    }
+
+    /// 能量 VAD 对最近一帧的判定，用于控制 AGC 的增益调整
+    pub fn is_speech(&self) -> bool {
+        self.energy.is_speech()
+    }
+
+    /// 从第一个语音帧到现在的时长（秒），尚未检测到语音时为 0
+    pub fn utterance_seconds(&self) -> f32 {
+        self.energy.utterance_seconds()
+    }
+
+    /// 断句后开始新的语句
+    pub fn reset(&mut self) {
+        self.energy.reset();
+        if let Some(ref mut neural) = self.neural {
+            neural.segment_ended = false;
+        }
+        self.waiting_logged = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vad::{FrameVad, VadDetector, VadParams};
+
+    fn endpointer(strategy: EndpointStrategy) -> Endpointer {
+        let vad = FrameVad::new(VadDetector::new(0.01), 16000, VadParams::default());
+        let params = EndpointParams {
+            min_silence_ms: 1000,
+            min_speech_ms: 500,
+            max_utterance_ms: 5000,
+        };
+        Endpointer::new(strategy, EndpointDetector::new(vad, params))
+    }
+
+    fn speech(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.1).sin()).collect()
+    }
+
+    #[test]
+    fn test_strategy_from_str() {
+        assert_eq!(
+            "energy".parse::<EndpointStrategy>().unwrap(),
+            EndpointStrategy::Energy
+        );
+        assert_eq!(
+            "Hybrid".parse::<EndpointStrategy>().unwrap(),
+            EndpointStrategy::Hybrid
+        );
+        assert!("silero".parse::<EndpointStrategy>().is_err());
+        assert_eq!(EndpointStrategy::Neural.to_string(), "neural");
+    }
+
+    #[test]
+    fn test_energy_strategy_reason() {
+        let mut endpointer = endpointer(EndpointStrategy::Energy);
+        endpointer.accept_waveform(&speech(16000));
+        assert!(endpointer.check(|| true).is_none());
+
+        endpointer.accept_waveform(&vec![0.0; 24000]);
+        match endpointer.check(|| unreachable!()) {
+            Some(EndpointReason::Silence {
+                speech_seconds,
+                silence_seconds,
+            }) => {
+                // 包含 100 ms 拖尾
+                assert!((speech_seconds - 1.1).abs() < 1e-3);
+                assert!(silence_seconds >= 1.0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_recognizer_strategy_ignores_energy() {
+        let mut endpointer = endpointer(EndpointStrategy::Recognizer);
+        endpointer.accept_waveform(&speech(16000));
+        endpointer.accept_waveform(&vec![0.0; 24000]);
+        assert!(endpointer.check(|| false).is_none());
+        assert_eq!(endpointer.check(|| true), Some(EndpointReason::Recognizer));
+    }
+
+    #[test]
+    fn test_hybrid_requires_both() {
+        let mut endpointer = endpointer(EndpointStrategy::Hybrid);
+        endpointer.accept_waveform(&speech(16000));
+        // 识别器规则满足、VAD 未满足
+        assert!(endpointer.check(|| true).is_none());
+
+        endpointer.accept_waveform(&vec![0.0; 24000]);
+        // VAD 满足、识别器规则未满足
+        assert!(endpointer.check(|| false).is_none());
+
+        let reason = endpointer.check(|| true).unwrap();
+        assert!(matches!(reason, EndpointReason::Hybrid { .. }));
+        assert!(reason.to_string().starts_with("识别器规则 + 能量 VAD"));
+    }
+
+    #[test]
+    fn test_max_utterance_overrides_strategy() {
+        let mut endpointer = endpointer(EndpointStrategy::Recognizer);
+        endpointer.accept_waveform(&speech(16000 * 5));
+        assert_eq!(
+            endpointer.check(|| false),
+            Some(EndpointReason::MaxUtterance { seconds: 5.0 })
+        );
+
+        endpointer.reset();
+        assert!(endpointer.check(|| false).is_none());
+    }
+
+    #[test]
+    fn test_recognizer_rules_from_config() {
+        let config = Config {
+            endpoint_min_silence_ms: 800,
+            endpoint_max_utterance_ms: 0,
+            ..Config::default()
+        };
+        let rules = recognizer_rules(&config);
+        assert_eq!(rules.rule2_min_trailing_silence, 0.8);
+        assert_eq!(rules.rule1_min_trailing_silence, 1.6);
+        assert_eq!(rules.rule3_min_utterance_length, RULE3_DISABLED_SECONDS);
+    }
+}
@@ -0,0 +1,576 @@
+use crate::export::is_cjk;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+/// 对齐结果每行的最大显示宽度（列），超出时分段显示
+const ALIGNMENT_WIDTH: usize = 100;
+/// 对齐结果行首标签（`REF:` 等）的宽度
+const LABEL_WIDTH: usize = 5;
+
+/// 清单中的一条：音频文件和参考文本
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub audio: PathBuf,
+    pub reference: String,
+}
+
+/// 读取评测清单
+///
+/// 每行一条 `<WAV 路径>\t<参考文本>`，必须用制表符分隔，路径中可以包含空格。
+/// 空行和 `#` 开头的行忽略，相对路径相对于清单所在目录。
+pub fn read_manifest(path: &Path) -> Result<Vec<ManifestEntry>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("无法读取评测清单 {}", path.display()))?;
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+    let entries = parse_manifest(&text, base)?;
+    for entry in &entries {
+        if !entry.audio.is_file() {
+            anyhow::bail!("评测清单中的音频不存在: {}", entry.audio.display());
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_manifest(text: &str, base: &Path) -> Result<Vec<ManifestEntry>> {
+    let mut entries = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (audio, reference) = line.split_once('\t').with_context(|| {
+            format!(
+                "评测清单第 {} 行缺少参考文本（路径和文本之间需要制表符）",
+                number + 1
+            )
+        })?;
+        entries.push(ManifestEntry {
+            audio: base.join(audio.trim()),
+            reference: reference.trim().to_string(),
+        });
+    }
+    if entries.is_empty() {
+        anyhow::bail!("评测清单为空");
+    }
+    Ok(entries)
+}
+
+/// 错误率的计算单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    /// 字错误率：中日韩文字每个字一个单位，夹杂的拉丁文字每个词一个单位
+    Cer,
+    /// 词错误率：按空白分词
+    Wer,
+}
+
+impl Metric {
+    /// 参考文本中有中日韩文字时用 CER，否则用 WER
+    pub fn detect(reference: &str) -> Self {
+        if reference.chars().any(is_cjk) {
+            Self::Cer
+        } else {
+            Self::Wer
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cer => write!(f, "CER"),
+            Self::Wer => write!(f, "WER"),
+        }
+    }
+}
+
+/// 把文本规范化后切分为评分单位
+///
+/// 转为小写，去掉标点（词中的撇号保留），中日韩文字每个字单独成为一个
+/// 单位，其余按空白分词。
+pub fn units(text: &str) -> Vec<String> {
+    let mut units = Vec::new();
+    let mut word = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let keep = c.is_alphanumeric() || c == '\'';
+        if !keep || is_cjk(c) {
+            if !word.is_empty() {
+                units.push(std::mem::take(&mut word));
+            }
+            if keep {
+                units.push(c.to_string());
+            }
+            continue;
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        units.push(word);
+    }
+    // 只由撇号组成的残片（如引号）不算词
+    units.retain(|u| u.chars().any(char::is_alphanumeric));
+    units
+}
+
+/// 对齐中的一步
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Match(String),
+    Substitution {
+        reference: String,
+        hypothesis: String,
+    },
+    Insertion(String),
+    Deletion(String),
+}
+
+/// jsonl 记录中的一步对齐：`op` 为 `C`（匹配）、`S`、`I`、`D`，缺失的一侧省略
+#[derive(Debug, Serialize)]
+pub struct EditRecord<'a> {
+    op: &'static str,
+    #[serde(rename = "ref", skip_serializing_if = "Option::is_none")]
+    reference: Option<&'a str>,
+    #[serde(rename = "hyp", skip_serializing_if = "Option::is_none")]
+    hypothesis: Option<&'a str>,
+}
+
+impl<'a> From<&'a Edit> for EditRecord<'a> {
+    fn from(edit: &'a Edit) -> Self {
+        let (op, reference, hypothesis) = match edit {
+            Edit::Match(unit) => ("C", Some(unit), Some(unit)),
+            Edit::Substitution {
+                reference,
+                hypothesis,
+            } => ("S", Some(reference), Some(hypothesis)),
+            Edit::Insertion(unit) => ("I", None, Some(unit)),
+            Edit::Deletion(unit) => ("D", Some(unit), None),
+        };
+        Self {
+            op,
+            reference: reference.map(String::as_str),
+            hypothesis: hypothesis.map(String::as_str),
+        }
+    }
+}
+
+/// 替换、插入、删除的数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ErrorCounts {
+    /// 参考文本的单位数
+    pub reference: usize,
+    pub substitutions: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl ErrorCounts {
+    pub fn errors(&self) -> usize {
+        self.substitutions + self.insertions + self.deletions
+    }
+
+    /// 错误率；参考文本为空时按一个单位计算，避免除零
+    pub fn rate(&self) -> f64 {
+        self.errors() as f64 / self.reference.max(1) as f64
+    }
+
+    pub fn add(&mut self, other: &ErrorCounts) {
+        self.reference += other.reference;
+        self.substitutions += other.substitutions;
+        self.insertions += other.insertions;
+        self.deletions += other.deletions;
+    }
+
+    fn from_edits(edits: &[Edit]) -> Self {
+        let mut counts = Self::default();
+        for edit in edits {
+            match edit {
+                Edit::Match(_) => counts.reference += 1,
+                Edit::Substitution { .. } => {
+                    counts.reference += 1;
+                    counts.substitutions += 1;
+                }
+                Edit::Insertion(_) => counts.insertions += 1,
+                Edit::Deletion(_) => {
+                    counts.reference += 1;
+                    counts.deletions += 1;
+                }
+            }
+        }
+        counts
+    }
+}
+
+impl std::fmt::Display for ErrorCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "N={} S={} I={} D={}",
+            self.reference, self.substitutions, self.insertions, self.deletions
+        )
+    }
+}
+
+/// 按最小编辑距离对齐参考和识别结果
+///
+/// 代价相同时优先匹配 / 替换，其次删除、插入，与 sclite 的对齐习惯一致。
+pub fn align(reference: &[String], hypothesis: &[String]) -> Vec<Edit> {
+    let (n, m) = (reference.len(), hypothesis.len());
+    let width = m + 1;
+    let mut cost = vec![0u32; (n + 1) * width];
+    for i in 0..=n {
+        cost[i * width] = i as u32;
+    }
+    for (j, cell) in cost[..width].iter_mut().enumerate() {
+        *cell = j as u32;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let diagonal =
+                cost[(i - 1) * width + j - 1] + u32::from(reference[i - 1] != hypothesis[j - 1]);
+            let deletion = cost[(i - 1) * width + j] + 1;
+            let insertion = cost[i * width + j - 1] + 1;
+            cost[i * width + j] = diagonal.min(deletion).min(insertion);
+        }
+    }
+
+    let mut edits = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        let current = cost[i * width + j];
+        if i > 0 && j > 0 {
+            let same = reference[i - 1] == hypothesis[j - 1];
+            if current == cost[(i - 1) * width + j - 1] + u32::from(!same) {
+                edits.push(if same {
+                    Edit::Match(reference[i - 1].clone())
+                } else {
+                    Edit::Substitution {
+                        reference: reference[i - 1].clone(),
+                        hypothesis: hypothesis[j - 1].clone(),
+                    }
+                });
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && current == cost[(i - 1) * width + j] + 1 {
+            edits.push(Edit::Deletion(reference[i - 1].clone()));
+            i -= 1;
+        } else {
+            edits.push(Edit::Insertion(hypothesis[j - 1].clone()));
+            j -= 1;
+        }
+    }
+    edits.reverse();
+    edits
+}
+
+/// 一个文件的评测结果
+#[derive(Debug, Clone)]
+pub struct FileResult {
+    pub audio: PathBuf,
+    pub metric: Metric,
+    pub reference: String,
+    pub hypothesis: String,
+    pub edits: Vec<Edit>,
+    pub counts: ErrorCounts,
+}
+
+impl FileResult {
+    pub fn new(audio: &Path, reference: &str, hypothesis: &str) -> Self {
+        let edits = align(&units(reference), &units(hypothesis));
+        Self {
+            audio: audio.to_path_buf(),
+            metric: Metric::detect(reference),
+            reference: reference.to_string(),
+            hypothesis: hypothesis.to_string(),
+            counts: ErrorCounts::from_edits(&edits),
+            edits,
+        }
+    }
+
+    /// sclite 风格的对齐：`REF`、`HYP` 两行逐列对应，`EVAL` 行标出 S / I / D，
+    /// 缺失的一侧用 `***` 占位
+    pub fn alignment(&self) -> String {
+        // 每列为 [参考, 识别结果, 标记]，超出行宽时另起一段
+        let mut blocks: Vec<Vec<[&str; 3]>> = vec![Vec::new()];
+        let mut width = 0;
+        for edit in &self.edits {
+            let cells = match edit {
+                Edit::Match(unit) => [unit.as_str(), unit.as_str(), ""],
+                Edit::Substitution {
+                    reference,
+                    hypothesis,
+                } => [reference.as_str(), hypothesis.as_str(), "S"],
+                Edit::Insertion(unit) => ["***", unit.as_str(), "I"],
+                Edit::Deletion(unit) => [unit.as_str(), "***", "D"],
+            };
+            let column = cells[0].width().max(cells[1].width()) + 1;
+            let block = blocks.last_mut().expect("至少有一段");
+            if !block.is_empty() && width + column > ALIGNMENT_WIDTH - LABEL_WIDTH {
+                blocks.push(Vec::new());
+                width = 0;
+            }
+            width += column;
+            blocks.last_mut().expect("至少有一段").push(cells);
+        }
+
+        blocks
+            .iter()
+            .map(|columns| {
+                ["REF:", "HYP:", "EVAL:"]
+                    .iter()
+                    .enumerate()
+                    .map(|(row, label)| {
+                        let mut line = format!("{:<width$}", label, width = LABEL_WIDTH);
+                        for cells in columns {
+                            let column = cells[0].width().max(cells[1].width());
+                            line.push(' ');
+                            line.push_str(cells[row]);
+                            line.push_str(&" ".repeat(column - cells[row].width()));
+                        }
+                        line.trim_end().to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// 部分文件转写失败
+///
+/// 每个失败的文件已经单独报告（jsonl 模式下为 `error` 记录），
+/// 调用方只需以失败状态退出，不必再输出一条错误。
+#[derive(Debug)]
+pub struct FailedFiles {
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl std::fmt::Display for FailedFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} / {} 个文件转写失败，未计入错误率",
+            self.failed, self.total
+        )
+    }
+}
+
+impl std::error::Error for FailedFiles {}
+
+/// 按计算单位分别累计的总体结果
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    /// `(单位, 文件数, 累计错误)`，按第一次出现的顺序
+    totals: Vec<(Metric, usize, ErrorCounts)>,
+    /// 转写失败、未计入错误率的文件数
+    failed: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, result: &FileResult) {
+        match self.totals.iter_mut().find(|(m, ..)| *m == result.metric) {
+            Some((_, files, counts)) => {
+                *files += 1;
+                counts.add(&result.counts);
+            }
+            None => self.totals.push((result.metric, 1, result.counts)),
+        }
+    }
+
+    pub fn add_failure(&mut self) {
+        self.failed += 1;
+    }
+
+    pub fn totals(&self) -> &[(Metric, usize, ErrorCounts)] {
+        &self.totals
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+}
+
+/// `--format jsonl` 下每个文件一行的记录
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record<'a> {
+    Eval {
+        audio: String,
+        metric: Metric,
+        reference: &'a str,
+        hypothesis: &'a str,
+        #[serde(flatten)]
+        counts: ErrorCounts,
+        rate: f64,
+        alignment: Vec<EditRecord<'a>>,
+    },
+    /// 转写失败的文件，不计入错误率
+    Error { audio: String, error: String },
+    Summary {
+        metric: Metric,
+        files: usize,
+        #[serde(flatten)]
+        counts: ErrorCounts,
+        rate: f64,
+    },
+}
+
+impl<'a> Record<'a> {
+    pub fn file(result: &'a FileResult) -> Self {
+        Self::Eval {
+            audio: result.audio.display().to_string(),
+            metric: result.metric,
+            reference: &result.reference,
+            hypothesis: &result.hypothesis,
+            counts: result.counts,
+            rate: result.counts.rate(),
+            alignment: result.edits.iter().map(EditRecord::from).collect(),
+        }
+    }
+
+    pub fn error(audio: &Path, error: &anyhow::Error) -> Self {
+        Self::Error {
+            audio: audio.display().to_string(),
+            error: format!("{:#}", error),
+        }
+    }
+
+    pub fn summary(metric: Metric, files: usize, counts: ErrorCounts) -> Self {
+        Self::Summary {
+            metric,
+            files,
+            counts,
+            rate: counts.rate(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let text = "# 评测集\n\na.wav\t今天 天气不错\nsub/my b.wav\tHello, world!\n";
+        let entries = parse_manifest(text, Path::new("/data")).unwrap();
+        assert_eq!(
+            entries,
+            [
+                ManifestEntry {
+                    audio: PathBuf::from("/data/a.wav"),
+                    reference: "今天 天气不错".to_string(),
+                },
+                ManifestEntry {
+                    audio: PathBuf::from("/data/sub/my b.wav"),
+                    reference: "Hello, world!".to_string(),
+                },
+            ]
+        );
+        assert!(parse_manifest("a.wav\n", Path::new(".")).is_err());
+        assert!(parse_manifest("a.wav 你好\n", Path::new(".")).is_err());
+        assert!(parse_manifest("# 只有注释\n", Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn test_units_normalize_text() {
+        assert_eq!(
+            units("今天，天气 不错。"),
+            ["今", "天", "天", "气", "不", "错"]
+        );
+        assert_eq!(units("Don't STOP, \"me\"!"), ["don't", "stop", "me"]);
+        assert_eq!(units("打开 WiFi 设置"), ["打", "开", "wifi", "设", "置"]);
+        assert_eq!(Metric::detect("打开 WiFi"), Metric::Cer);
+        assert_eq!(Metric::detect("hello world"), Metric::Wer);
+    }
+
+    #[test]
+    fn test_align_counts() {
+        let reference = strings(&["the", "cat", "sat", "on", "the", "mat"]);
+        let hypothesis = strings(&["the", "bat", "sat", "the", "mat", "today"]);
+        let edits = align(&reference, &hypothesis);
+        let counts = ErrorCounts::from_edits(&edits);
+        assert_eq!(
+            counts,
+            ErrorCounts {
+                reference: 6,
+                substitutions: 1,
+                insertions: 1,
+                deletions: 1,
+            }
+        );
+        assert!((counts.rate() - 0.5).abs() < 1e-9);
+        assert_eq!(edits[3], Edit::Deletion("on".to_string()));
+        assert_eq!(edits[6], Edit::Insertion("today".to_string()));
+
+        let empty = ErrorCounts::from_edits(&align(&[], &strings(&["uh"])));
+        assert_eq!(empty.insertions, 1);
+        assert_eq!(empty.rate(), 1.0);
+    }
+
+    #[test]
+    fn test_alignment_columns() {
+        let result = FileResult::new(Path::new("a.wav"), "今天天气好", "今天田气好啊");
+        assert_eq!(result.metric, Metric::Cer);
+        assert_eq!(result.counts.to_string(), "N=5 S=1 I=1 D=0");
+        assert_eq!(
+            result.alignment(),
+            "REF:  今 天 天 气 好 ***\n\
+             HYP:  今 天 田 气 好 啊\n\
+             EVAL:       S        I"
+        );
+    }
+
+    #[test]
+    fn test_file_record_includes_alignment() {
+        let result = FileResult::new(Path::new("a.wav"), "天气好", "田气好啊");
+        let record = serde_json::to_value(Record::file(&result)).unwrap();
+        assert_eq!(record["type"], "eval");
+        assert_eq!(
+            record["alignment"],
+            serde_json::json!([
+                {"op": "S", "ref": "天", "hyp": "田"},
+                {"op": "C", "ref": "气", "hyp": "气"},
+                {"op": "C", "ref": "好", "hyp": "好"},
+                {"op": "I", "hyp": "啊"},
+            ])
+        );
+        let result = FileResult::new(Path::new("a.wav"), "hello world", "hello");
+        let record = serde_json::to_value(Record::file(&result)).unwrap();
+        assert_eq!(
+            record["alignment"][1],
+            serde_json::json!({"op": "D", "ref": "world"})
+        );
+
+        let error = anyhow::anyhow!("无法打开");
+        let record = serde_json::to_value(Record::error(Path::new("b.wav"), &error)).unwrap();
+        assert_eq!(record["type"], "error");
+        assert_eq!(record["error"], "无法打开");
+    }
+
+    #[test]
+    fn test_summary_per_metric() {
+        let mut summary = Summary::default();
+        summary.add(&FileResult::new(Path::new("a.wav"), "你好", "你好"));
+        summary.add(&FileResult::new(Path::new("b.wav"), "hello world", "hello"));
+        summary.add(&FileResult::new(Path::new("c.wav"), "再见", "在见"));
+        let totals = summary.totals();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].0, Metric::Cer);
+        assert_eq!(totals[0].1, 2);
+        assert_eq!(totals[0].2.substitutions, 1);
+        assert_eq!(totals[0].2.reference, 4);
+        assert_eq!(totals[1].0, Metric::Wer);
+        assert_eq!(totals[1].2.deletions, 1);
+    }
+}
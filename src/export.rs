@@ -0,0 +1,381 @@
+use crate::config::Config;
+use crate::segmenter::Utterance;
+use crate::transcriber::Transcript;
+use crate::{ctm, subtitle, textgrid};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use unicode_width::UnicodeWidthChar;
+
+/// 单个词的最短 / 最长持续时间（秒）
+///
+/// 识别器只给出每个 token 的开始时间，词的结束时间取下一个词的开始，
+/// 但停顿前的词不会持续到停顿结束；最短时长只用于语句的最后一个词。
+const MIN_WORD_SECONDS: f64 = 0.2;
+const MAX_WORD_SECONDS: f64 = 1.0;
+
+/// 文件转写的导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// SubRip 字幕
+    Srt,
+    /// WebVTT 字幕
+    Vtt,
+    /// NIST CTM（SCTK 评测用的词级时间标注）
+    Ctm,
+    /// Praat TextGrid（语句和词两层标注）
+    TextGrid,
+}
+
+impl ExportFormat {
+    /// 输出文件的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::Vtt => "vtt",
+            Self::Ctm => "ctm",
+            Self::TextGrid => "TextGrid",
+        }
+    }
+
+    /// 按格式生成文件内容；`name` 为音频文件名（不含扩展名），CTM 用作文件标识
+    pub fn render(self, transcript: &Transcript, name: &str, config: &Config) -> String {
+        let utterances = &transcript.utterances;
+        match self {
+            Self::Srt => subtitle::to_srt(&subtitle::cues(utterances, config)),
+            Self::Vtt => subtitle::to_vtt(&subtitle::cues(utterances, config)),
+            Self::Ctm => ctm::to_ctm(utterances, name),
+            Self::TextGrid => textgrid::to_textgrid(utterances, transcript.duration),
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "srt" => Ok(Self::Srt),
+            "vtt" | "webvtt" => Ok(Self::Vtt),
+            "ctm" => Ok(Self::Ctm),
+            "textgrid" => Ok(Self::TextGrid),
+            _ => anyhow::bail!("无效的导出格式: {}（可选 srt、vtt、ctm、textgrid）", s),
+        }
+    }
+}
+
+/// 把转写结果按格式写入 `dir`（未指定时为音频所在目录）下与音频同名的文件
+pub fn write_export(
+    format: ExportFormat,
+    transcript: &Transcript,
+    config: &Config,
+    audio: &Path,
+    dir: Option<&Path>,
+) -> Result<PathBuf> {
+    let path = export_path(format, audio, dir);
+    let stem = audio.file_stem().unwrap_or(audio.as_os_str());
+    let content = format.render(transcript, &stem.to_string_lossy(), config);
+    std::fs::write(&path, content).with_context(|| format!("无法写入 {}", path.display()))?;
+    Ok(path)
+}
+
+/// 导出文件路径：音频文件名去掉最后一个扩展名后追加格式扩展名
+///
+/// 文件名中的其他点保留，`a.part1.wav` 导出为 `a.part1.srt`。
+pub fn export_path(format: ExportFormat, audio: &Path, dir: Option<&Path>) -> PathBuf {
+    let dir = dir
+        .or_else(|| audio.parent())
+        .unwrap_or_else(|| Path::new("."));
+    let stem = audio.file_stem().unwrap_or(audio.as_os_str());
+    dir.join(format!("{}.{}", stem.to_string_lossy(), format.extension()))
+}
+
+/// 检查多个音频文件的导出路径是否冲突，避免后一个文件覆盖前一个的结果
+pub fn check_export_paths(
+    format: ExportFormat,
+    files: &[PathBuf],
+    dir: Option<&Path>,
+) -> Result<()> {
+    let mut seen: Vec<(PathBuf, &Path)> = Vec::with_capacity(files.len());
+    for file in files {
+        let path = export_path(format, file, dir);
+        if let Some((_, other)) = seen.iter().find(|(p, _)| *p == path) {
+            anyhow::bail!(
+                "{} 和 {} 的导出文件相同（{}），请重命名其中一个或分别转写",
+                other.display(),
+                file.display(),
+                path.display()
+            );
+        }
+        seen.push((path, file));
+    }
+    Ok(())
+}
+
+/// 带时间的词；中日韩文字每个字是一个词
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedWord {
+    pub text: String,
+    /// 在输入音频中的起止时间（秒）
+    pub start: f64,
+    pub end: f64,
+    /// 与前一个词之间是否有空格（拉丁文字）
+    pub space_before: bool,
+    /// 置信度（0–1），为组成该词的各 token 置信度之积；模型不提供时为 `None`
+    pub confidence: Option<f32>,
+}
+
+/// 把语句拆成带时间的词
+///
+/// 有 token 时间戳时按 token 合并：`▁` 开头的 BPE token 开始一个新词，
+/// 中日韩文字单独成词，标点附在前一个词上。模型不提供时间戳时把文本
+/// 拆成词后在语句时间范围内均匀分布。
+pub fn timed_words(utterance: &Utterance) -> Vec<TimedWord> {
+    let timed =
+        !utterance.tokens.is_empty() && utterance.timestamps.len() == utterance.tokens.len();
+    let confident = utterance.confidences.len() == utterance.tokens.len();
+    let mut words = if timed {
+        let pieces = utterance.tokens.iter().enumerate().map(|(i, token)| Piece {
+            text: token.replace('▁', " "),
+            start: Some(utterance.timestamps[i] as f64),
+            confidence: confident.then(|| utterance.confidences[i]),
+        });
+        group_words(pieces)
+    } else {
+        let pieces = utterance
+            .text
+            .split_inclusive(char::is_whitespace)
+            .flat_map(|word| split_cjk(word).into_iter())
+            .map(|text| Piece {
+                text,
+                start: None,
+                confidence: None,
+            });
+        group_words(pieces)
+    };
+
+    if !timed {
+        let step = (utterance.end - utterance.start).max(0.0) / words.len().max(1) as f64;
+        for (i, word) in words.iter_mut().enumerate() {
+            word.start = utterance.start + step * i as f64;
+        }
+    }
+
+    let starts: Vec<f64> = words.iter().map(|w| w.start).collect();
+    for (i, word) in words.iter_mut().enumerate() {
+        word.end = match starts.get(i + 1) {
+            Some(&next) => next.min(word.start + MAX_WORD_SECONDS),
+            None => utterance
+                .end
+                .min(word.start + MAX_WORD_SECONDS)
+                .max(word.start + MIN_WORD_SECONDS),
+        };
+    }
+    words
+}
+
+/// 组成词的文本片段（token 或拆分后的文字）
+struct Piece {
+    text: String,
+    start: Option<f64>,
+    confidence: Option<f32>,
+}
+
+/// 按字符类别把文本片段合并成词；片段开头的空格表示新词
+fn group_words(pieces: impl Iterator<Item = Piece>) -> Vec<TimedWord> {
+    let mut words: Vec<TimedWord> = Vec::new();
+    let mut space_pending = false;
+    for Piece {
+        text: piece,
+        start,
+        confidence,
+    } in pieces
+    {
+        let starts_word = piece.starts_with(char::is_whitespace);
+        let text = piece.trim();
+        if text.is_empty() {
+            space_pending |= starts_word;
+            continue;
+        }
+        let first = text.chars().next().unwrap_or(' ');
+        let last_is_cjk = words
+            .last()
+            .and_then(|w| w.text.chars().last())
+            .is_some_and(is_cjk);
+        let new_word = !is_punctuation(first)
+            && (words.is_empty() || starts_word || space_pending || is_cjk(first) || last_is_cjk);
+
+        match words.last_mut() {
+            Some(word) if !new_word => {
+                word.text.push_str(text);
+                word.confidence = word.confidence.zip(confidence).map(|(a, b)| a * b);
+            }
+            _ => words.push(TimedWord {
+                text: text.to_string(),
+                start: start.unwrap_or(0.0),
+                end: 0.0,
+                space_before: !words.is_empty() && (starts_word || space_pending),
+                confidence,
+            }),
+        }
+        space_pending = piece.ends_with(char::is_whitespace);
+    }
+    words
+}
+
+/// 把中日韩文字拆成单字，其余部分保持原样
+fn split_cjk(text: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            if !current.is_empty() {
+                pieces.push(std::mem::take(&mut current));
+            }
+            pieces.push(c.to_string());
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// 中日韩文字（按显示宽度判断，全角标点除外）
+pub fn is_cjk(c: char) -> bool {
+    c.width() == Some(2) && !is_punctuation(c)
+}
+
+pub fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '，' | '。'
+                | '、'
+                | '；'
+                | '：'
+                | '？'
+                | '！'
+                | '…'
+                | '“'
+                | '”'
+                | '‘'
+                | '’'
+                | '（'
+                | '）'
+                | '《'
+                | '》'
+                | '—'
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utterance(text: &str, tokens: &[&str], timestamps: &[f32]) -> Utterance {
+        Utterance {
+            text: text.to_string(),
+            tokens: tokens.iter().map(|t| t.to_string()).collect(),
+            timestamps: timestamps.to_vec(),
+            start: 1.0,
+            end: 3.0,
+            ..Utterance::default()
+        }
+    }
+
+    fn texts(words: &[TimedWord]) -> Vec<&str> {
+        words.iter().map(|w| w.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_export_format_from_str() {
+        assert_eq!("SRT".parse::<ExportFormat>().unwrap(), ExportFormat::Srt);
+        assert_eq!("webvtt".parse::<ExportFormat>().unwrap(), ExportFormat::Vtt);
+        assert_eq!(
+            "TextGrid".parse::<ExportFormat>().unwrap(),
+            ExportFormat::TextGrid
+        );
+        assert!("ass".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_export_path_keeps_dots_in_name() {
+        let path = export_path(ExportFormat::Srt, Path::new("/data/a.part1.wav"), None);
+        assert_eq!(path, Path::new("/data/a.part1.srt"));
+        let path = export_path(
+            ExportFormat::TextGrid,
+            Path::new("a.part2.wav"),
+            Some(Path::new("out")),
+        );
+        assert_eq!(path, Path::new("out/a.part2.TextGrid"));
+    }
+
+    #[test]
+    fn test_check_export_paths() {
+        let files = [PathBuf::from("a.part1.wav"), PathBuf::from("a.part2.wav")];
+        assert!(check_export_paths(ExportFormat::Srt, &files, None).is_ok());
+
+        let files = [PathBuf::from("x/a.wav"), PathBuf::from("y/a.wav")];
+        assert!(check_export_paths(ExportFormat::Ctm, &files, None).is_ok());
+        let err = check_export_paths(ExportFormat::Ctm, &files, Some(Path::new("out")))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("out/a.ctm"));
+    }
+
+    #[test]
+    fn test_timed_words_cjk_tokens() {
+        let u = utterance(
+            "你好，世界",
+            &["你", "好", "，", "世", "界"],
+            &[1.0, 1.2, 1.3, 1.6, 1.8],
+        );
+        let words = timed_words(&u);
+        assert_eq!(texts(&words), ["你", "好，", "世", "界"]);
+        assert_eq!(words[1].start, 1.2f32 as f64);
+        assert_eq!(words[1].end, 1.6f32 as f64);
+        // 最后一个词持续到语句结束，但不超过最长词时长
+        assert!((words[3].end - 2.8).abs() < 1e-6);
+        assert!(!words[1].space_before);
+    }
+
+    #[test]
+    fn test_timed_words_bpe_tokens() {
+        let u = utterance(
+            "HELLO WORLD",
+            &["▁HE", "LLO", "▁WOR", "LD"],
+            &[1.0, 1.1, 1.5, 1.7],
+        );
+        let words = timed_words(&u);
+        assert_eq!(texts(&words), ["HELLO", "WORLD"]);
+        assert!(!words[0].space_before);
+        assert!(words[1].space_before);
+        assert_eq!(words[0].end, 1.5f32 as f64);
+        assert_eq!(words[0].confidence, None);
+    }
+
+    #[test]
+    fn test_timed_words_confidence_is_product_of_tokens() {
+        let mut u = utterance("HELLO 你", &["▁HE", "LLO", "你"], &[1.0, 1.05, 1.1]);
+        u.confidences = vec![0.5, 0.8, 0.9];
+        let words = timed_words(&u);
+        assert_eq!(texts(&words), ["HELLO", "你"]);
+        assert!((words[0].confidence.unwrap() - 0.4).abs() < 1e-6);
+        assert_eq!(words[1].confidence, Some(0.9));
+        // 相邻 token 间隔很短时词不重叠
+        assert!(words[0].end <= words[1].start);
+    }
+
+    #[test]
+    fn test_timed_words_without_timestamps_spread_evenly() {
+        let u = utterance("今天 good morning", &[], &[]);
+        let words = timed_words(&u);
+        assert_eq!(texts(&words), ["今", "天", "good", "morning"]);
+        assert_eq!(words[0].start, 1.0);
+        assert_eq!(words[2].start, 2.0);
+        assert!(words[3].space_before);
+        assert_eq!(words[3].end, 3.0);
+    }
+}
@@ -102,6 +102,41 @@ pub struct SherpaOnnxOnlineRecognizerResult {
     pub json: *const c_char,
 }
 
+#[repr(C)]
+pub struct SherpaOnnxVoiceActivityDetector {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct SherpaOnnxSileroVadModelConfig {
+    pub model: *const c_char,
+    pub threshold: c_float,
+    pub min_silence_duration: c_float,
+    pub min_speech_duration: c_float,
+    pub window_size: c_int,
+    pub max_speech_duration: c_float,
+}
+
+#[repr(C)]
+pub struct SherpaOnnxTenVadModelConfig {
+    pub model: *const c_char,
+    pub threshold: c_float,
+    pub min_silence_duration: c_float,
+    pub min_speech_duration: c_float,
+    pub window_size: c_int,
+    pub max_speech_duration: c_float,
+}
+
+#[repr(C)]
+pub struct SherpaOnnxVadModelConfig {
+    pub silero_vad: SherpaOnnxSileroVadModelConfig,
+    pub sample_rate: c_int,
+    pub num_threads: c_int,
+    pub provider: *const c_char,
+    pub debug: c_int,
+    pub ten_vad: SherpaOnnxTenVadModelConfig,
+}
+
 #[allow(dead_code)]
 #[link(name = "sherpa-onnx-c-api")]
 extern "C" {
@@ -141,9 +176,76 @@ extern "C" {
 
     pub fn SherpaOnnxDestroyOnlineRecognizerResult(result: *const SherpaOnnxOnlineRecognizerResult);
 
-    pub fn SherpaOnnxOnlineStreamIsEndpoint(stream: *mut SherpaOnnxOnlineStream) -> c_int;
+    pub fn SherpaOnnxOnlineStreamInputFinished(stream: *mut SherpaOnnxOnlineStream);
+
+    pub fn SherpaOnnxOnlineStreamIsEndpoint(
+        recognizer: *const SherpaOnnxOnlineRecognizer,
+        stream: *const SherpaOnnxOnlineStream,
+    ) -> c_int;
+
+    pub fn SherpaOnnxOnlineStreamReset(
+        recognizer: *const SherpaOnnxOnlineRecognizer,
+        stream: *const SherpaOnnxOnlineStream,
+    );
+
+    pub fn SherpaOnnxCreateVoiceActivityDetector(
+        config: *const SherpaOnnxVadModelConfig,
+        buffer_size_in_seconds: c_float,
+    ) -> *const SherpaOnnxVoiceActivityDetector;
 
-    pub fn SherpaOnnxOnlineStreamReset(stream: *mut SherpaOnnxOnlineStream);
+    pub fn SherpaOnnxDestroyVoiceActivityDetector(vad: *const SherpaOnnxVoiceActivityDetector);
+
+    pub fn SherpaOnnxVoiceActivityDetectorAcceptWaveform(
+        vad: *const SherpaOnnxVoiceActivityDetector,
+        samples: *const c_float,
+        n: c_int,
+    );
+
+    pub fn SherpaOnnxVoiceActivityDetectorEmpty(
+        vad: *const SherpaOnnxVoiceActivityDetector,
+    ) -> c_int;
+
+    pub fn SherpaOnnxVoiceActivityDetectorDetected(
+        vad: *const SherpaOnnxVoiceActivityDetector,
+    ) -> c_int;
+
+    pub fn SherpaOnnxVoiceActivityDetectorPop(vad: *const SherpaOnnxVoiceActivityDetector);
+
+    pub fn SherpaOnnxVoiceActivityDetectorReset(vad: *const SherpaOnnxVoiceActivityDetector);
+}
+
+/// sherpa-onnx 内置断句规则（秒）
+///
+/// - 规则 1：尚未识别出文字时，尾部静音达到 `rule1_min_trailing_silence`
+/// - 规则 2：已识别出文字后，尾部静音达到 `rule2_min_trailing_silence`
+/// - 规则 3：语句长度达到 `rule3_min_utterance_length`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EndpointRules {
+    pub rule1_min_trailing_silence: f32,
+    pub rule2_min_trailing_silence: f32,
+    pub rule3_min_utterance_length: f32,
+}
+
+impl Default for EndpointRules {
+    fn default() -> Self {
+        Self {
+            rule1_min_trailing_silence: 2.4,
+            rule2_min_trailing_silence: 1.2,
+            rule3_min_utterance_length: 20.0,
+        }
+    }
+}
+
+/// 带 token 和时间戳的识别结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecognitionResult {
+    pub text: String,
+    pub tokens: Vec<String>,
+    /// 每个 token 相对本句开始（上次重置识别流）的时间（秒）；模型不提供时为空
+    pub timestamps: Vec<f32>,
+    /// 每个 token 的置信度（0–1），由结果 JSON 中的对数概率 `ys_probs` 换算；
+    /// 模型不提供时为空
+    pub confidences: Vec<f32>,
 }
 
 pub struct OnlineRecognizer {
@@ -171,6 +273,7 @@ impl OnlineRecognizer {
         decoder: &str,
         tokens: &str,
         num_threads: i32,
+        rules: EndpointRules,
     ) -> anyhow::Result<Self> {
         unsafe {
             let encoder_c = CString::new(encoder).unwrap();
@@ -209,9 +312,9 @@ impl OnlineRecognizer {
                 decoding_method: decoding_c.as_ptr(),
                 max_active_paths: 4,
                 enable_endpoint: 1,
-                rule1_min_trailing_silence: 2.4,
-                rule2_min_trailing_silence: 1.2,
-                rule3_min_utterance_length: 0.0,
+                rule1_min_trailing_silence: rules.rule1_min_trailing_silence,
+                rule2_min_trailing_silence: rules.rule2_min_trailing_silence,
+                rule3_min_utterance_length: rules.rule3_min_utterance_length,
                 hotwords_file: ptr::null(),
                 hotwords_score: 0.0,
                 ctc_fst_decoder_config: SherpaOnnxOnlineCtcFstDecoderConfig {
@@ -280,24 +383,62 @@ impl OnlineRecognizer {
         }
     }
 
-    /// 已弃用：使用 `vad::EndpointDetector` 替代
-    ///
-    /// sherpa-onnx 的 endpoint 检测在某些平台上存在崩溃问题。
-    /// 推荐使用 `vad::EndpointDetector` 进行基于 VAD 和静音时长的 endpoint 检测。
-    #[deprecated(since = "1.2.3", note = "使用 vad::EndpointDetector 替代")]
-    #[allow(dead_code)]
+    /// 获取当前识别结果及其 token 和时间戳
+    pub fn get_detailed_result(&self, stream: &OnlineStream) -> RecognitionResult {
+        unsafe {
+            let result = SherpaOnnxGetOnlineStreamResult(self.recognizer, stream.stream);
+            if result.is_null() {
+                return RecognitionResult::default();
+            }
+            let r = &*result;
+            let text = if r.text.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(r.text).to_string_lossy().to_string()
+            };
+            let count = r.count.max(0) as usize;
+            let tokens = if r.tokens_arr.is_null() {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(r.tokens_arr, count)
+                    .iter()
+                    .map(|&token| CStr::from_ptr(token).to_string_lossy().to_string())
+                    .collect()
+            };
+            // 模型不提供时间戳时该指针为空
+            let timestamps = if r.timestamps.is_null() {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(r.timestamps, count).to_vec()
+            };
+            let confidences = if r.json.is_null() {
+                Vec::new()
+            } else {
+                token_confidences(&CStr::from_ptr(r.json).to_string_lossy(), count)
+            };
+            SherpaOnnxDestroyOnlineRecognizerResult(result);
+
+            RecognitionResult {
+                text,
+                tokens,
+                timestamps,
+                confidences,
+            }
+        }
+    }
+
+    /// 按创建识别器时的 `EndpointRules` 判断是否到达断句点
     pub fn is_endpoint(&self, stream: &OnlineStream) -> bool {
         if stream.stream.is_null() {
             eprintln!("[WARNING] stream.stream is null in is_endpoint");
             return false;
         }
-        // 禁用以避免崩溃，使用 vad::EndpointDetector 替代
-        false
+        unsafe { SherpaOnnxOnlineStreamIsEndpoint(self.recognizer, stream.stream) != 0 }
     }
 
     pub fn reset(&self, stream: &mut OnlineStream) {
         unsafe {
-            SherpaOnnxOnlineStreamReset(stream.stream);
+            SherpaOnnxOnlineStreamReset(self.recognizer, stream.stream);
         }
     }
 }
@@ -313,6 +454,116 @@ impl OnlineStream {
             );
         }
     }
+
+    /// 通知模型不会再有新的音频，之后可以解码出剩余的所有帧
+    pub fn input_finished(&mut self) {
+        unsafe {
+            SherpaOnnxOnlineStreamInputFinished(self.stream);
+        }
+    }
+}
+
+/// 从结果 JSON 的 `ys_probs`（每个 token 的对数概率）换算置信度
+///
+/// 字段缺失、无法解析或个数与 token 不一致时返回空。
+fn token_confidences(json: &str, count: usize) -> Vec<f32> {
+    let probs: Option<Vec<f32>> = serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| value.get("ys_probs").cloned())
+        .and_then(|probs| serde_json::from_value(probs).ok());
+    match probs {
+        Some(probs) if probs.len() == count && count > 0 => {
+            probs.iter().map(|p| p.exp().clamp(0.0, 1.0)).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// silero 神经网络 VAD（16 kHz）
+pub struct VoiceActivityDetector {
+    vad: *const SherpaOnnxVoiceActivityDetector,
+    _model: CString,
+    _provider: CString,
+}
+
+unsafe impl Send for VoiceActivityDetector {}
+
+impl VoiceActivityDetector {
+    /// 语音采样率，silero VAD 只支持 16 kHz（和 8 kHz）
+    pub const SAMPLE_RATE: u32 = 16000;
+
+    pub fn new(
+        model: &str,
+        threshold: f32,
+        min_silence_duration: f32,
+        min_speech_duration: f32,
+        max_speech_duration: f32,
+    ) -> anyhow::Result<Self> {
+        let model_c = CString::new(model)?;
+        let provider_c = CString::new("cpu").unwrap();
+        let config = SherpaOnnxVadModelConfig {
+            silero_vad: SherpaOnnxSileroVadModelConfig {
+                model: model_c.as_ptr(),
+                threshold,
+                min_silence_duration,
+                min_speech_duration,
+                window_size: 512,
+                max_speech_duration,
+            },
+            sample_rate: Self::SAMPLE_RATE as c_int,
+            num_threads: 1,
+            provider: provider_c.as_ptr(),
+            debug: 0,
+            ten_vad: SherpaOnnxTenVadModelConfig {
+                model: ptr::null(),
+                threshold: 0.0,
+                min_silence_duration: 0.0,
+                min_speech_duration: 0.0,
+                window_size: 0,
+                max_speech_duration: 0.0,
+            },
+        };
+
+        let vad = unsafe { SherpaOnnxCreateVoiceActivityDetector(&raw const config, 30.0) };
+        if vad.is_null() {
+            anyhow::bail!("创建 VAD 失败: {}", model);
+        }
+        Ok(Self {
+            vad,
+            _model: model_c,
+            _provider: provider_c,
+        })
+    }
+
+    pub fn accept_waveform(&mut self, samples: &[f32]) {
+        unsafe {
+            SherpaOnnxVoiceActivityDetectorAcceptWaveform(
+                self.vad,
+                samples.as_ptr(),
+                samples.len() as c_int,
+            );
+        }
+    }
+
+    /// 取出所有已结束的语音段，返回段数（只关心边界，丢弃段内音频）
+    pub fn pop_segments(&mut self) -> usize {
+        let mut count = 0;
+        unsafe {
+            while SherpaOnnxVoiceActivityDetectorEmpty(self.vad) == 0 {
+                SherpaOnnxVoiceActivityDetectorPop(self.vad);
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+impl Drop for VoiceActivityDetector {
+    fn drop(&mut self) {
+        unsafe {
+            SherpaOnnxDestroyVoiceActivityDetector(self.vad);
+        }
+    }
 }
 
 impl Drop for OnlineRecognizer {
@@ -330,3 +581,39 @@ impl Drop for OnlineStream {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn load_recognizer() -> OnlineRecognizer {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("models");
+        OnlineRecognizer::new(
+            &dir.join("encoder.int8.onnx").to_string_lossy(),
+            &dir.join("decoder.int8.onnx").to_string_lossy(),
+            &dir.join("tokens.txt").to_string_lossy(),
+            1,
+            EndpointRules::default(),
+        )
+        .expect("加载模型失败")
+    }
+
+    #[test]
+    #[ignore] // Requires models downloaded by setup_models.sh
+    fn test_is_endpoint_after_trailing_silence() {
+        let recognizer = load_recognizer();
+        let mut stream = recognizer.create_stream();
+        assert!(!recognizer.is_endpoint(&stream));
+
+        // 3 秒静音超过规则 1 的 2.4 秒
+        stream.accept_waveform(16000, &vec![0.0; 16000 * 3]);
+        while recognizer.is_ready(&stream) {
+            recognizer.decode(&mut stream);
+        }
+        assert!(recognizer.is_endpoint(&stream));
+
+        recognizer.reset(&mut stream);
+        assert!(!recognizer.is_endpoint(&stream));
+    }
+}
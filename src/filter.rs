@@ -0,0 +1,106 @@
+use std::f32::consts::PI;
+
+/// 二阶 Butterworth 高通滤波器
+///
+/// 去除直流偏置和低频隆隆声（桌面震动、空调低频等），避免这些能量
+/// 抬高 VAD 计算的均方能量。截止频率取 20–100 Hz 时不影响语音频带。
+/// 系数按 RBJ Audio EQ Cookbook 计算，使用转置直接 II 型结构。
+pub struct HighPassFilter {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl HighPassFilter {
+    /// # Arguments
+    /// * `sample_rate` - 采样率
+    /// * `cutoff_hz` - 截止频率（-3 dB 点）
+    pub fn new(sample_rate: u32, cutoff_hz: f32) -> Self {
+        let nyquist = sample_rate as f32 / 2.0;
+        let cutoff = cutoff_hz.clamp(1.0, nyquist * 0.9);
+        let w0 = 2.0 * PI * cutoff / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        // Q = 1/√2，Butterworth 响应
+        let alpha = sin / 2.0f32.sqrt();
+        let a0 = 1.0 + alpha;
+
+        Self {
+            b0: (1.0 + cos) / 2.0 / a0,
+            b1: -(1.0 + cos) / a0,
+            b2: (1.0 + cos) / 2.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            let x = *sample;
+            let y = self.b0 * x + self.z1;
+            self.z1 = self.b1 * x - self.a1 * y + self.z2;
+            self.z2 = self.b2 * x - self.a2 * y;
+            *sample = y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vad::VadDetector;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn tone(freq: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * PI * freq * i as f32 / sample_rate as f32).sin() * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn test_removes_dc_offset() {
+        let mut filter = HighPassFilter::new(16000, 80.0);
+        let mut samples = vec![0.2; 16000];
+        filter.process(&mut samples);
+        assert!(samples[8000..].iter().all(|s| s.abs() < 1e-4));
+    }
+
+    #[test]
+    fn test_dc_offset_does_not_trigger_vad() {
+        let mut vad = VadDetector::new(0.01);
+        let mut filter = HighPassFilter::new(48000, 80.0);
+        // 直流偏置 0.15，叠加轻微噪声
+        let mut samples: Vec<f32> = (0..48000).map(|i| 0.15 + (i as f32).sin() * 0.01).collect();
+        assert!(vad.detect(&samples));
+
+        filter.process(&mut samples);
+        assert!(!vad.detect(&samples[24000..]));
+    }
+
+    #[test]
+    fn test_attenuates_rumble() {
+        let mut filter = HighPassFilter::new(16000, 100.0);
+        let mut samples = tone(20.0, 16000, 32000);
+        let input = rms(&samples);
+        filter.process(&mut samples);
+        // 二阶滤波器在截止频率以下按 12 dB/倍频程衰减
+        assert!(rms(&samples[16000..]) < input * 0.1);
+    }
+
+    #[test]
+    fn test_passes_speech_band() {
+        let mut filter = HighPassFilter::new(16000, 80.0);
+        let mut samples = tone(1000.0, 16000, 16000);
+        let expected = rms(&samples);
+        filter.process(&mut samples);
+        assert!((rms(&samples[8000..]) / expected - 1.0).abs() < 0.02);
+    }
+}
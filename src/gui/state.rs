@@ -17,6 +17,8 @@ pub enum AppState {
 pub struct StateManager {
     state: Arc<Mutex<AppState>>,
     recognized_text: Arc<Mutex<String>>,
+    device_warning: Arc<Mutex<Option<String>>>,
+    capture_warning: Arc<Mutex<Option<String>>>,
 }
 
 impl StateManager {
@@ -25,6 +27,8 @@ impl StateManager {
         Self {
             state: Arc::new(Mutex::new(AppState::Idle)),
             recognized_text: Arc::new(Mutex::new(String::new())),
+            device_warning: Arc::new(Mutex::new(None)),
+            capture_warning: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -53,6 +57,26 @@ impl StateManager {
         self.recognized_text.lock().unwrap().clear();
     }
 
+    /// 获取设备告警（断开重连、使用默认设备等），设备正常时为 `None`
+    pub fn get_device_warning(&self) -> Option<String> {
+        self.device_warning.lock().unwrap().clone()
+    }
+
+    /// 设置设备告警
+    pub fn set_device_warning(&self, warning: Option<String>) {
+        *self.device_warning.lock().unwrap() = warning;
+    }
+
+    /// 获取采集告警（缓冲区溢出丢弃了音频），没有丢失数据时为 `None`
+    pub fn get_capture_warning(&self) -> Option<String> {
+        self.capture_warning.lock().unwrap().clone()
+    }
+
+    /// 设置采集告警
+    pub fn set_capture_warning(&self, warning: Option<String>) {
+        *self.capture_warning.lock().unwrap() = warning;
+    }
+
     /// 获取状态描述
     pub fn get_state_description(&self) -> &'static str {
         match self.get_state() {
@@ -1,8 +1,11 @@
 use super::hotkey::HotkeyManager;
 use super::state::{AppState, StateManager};
+use crate::audio::DeviceStatus;
+use crate::config::Config;
 use crate::ffi::OnlineStream;
 use crate::injector::TextInjector;
 use crate::recognizer::RecognizerEngine;
+use crate::segmenter::SourceEvent;
 use crate::wayland;
 use eframe::egui;
 use std::sync::{Arc, Mutex};
@@ -18,11 +21,15 @@ pub struct CinnabarWindow {
 
 impl CinnabarWindow {
     /// 创建新的悬浮窗实例
-    pub fn new(_cc: &eframe::CreationContext<'_>, model_dir: &std::path::Path) -> Self {
-        let mut recognizer = RecognizerEngine::new(model_dir, None, None).ok();
+    pub fn new(_cc: &eframe::CreationContext<'_>, config: &Config) -> Self {
+        let mut recognizer = RecognizerEngine::new(config, None, None).ok();
 
         if let Some(ref mut r) = recognizer {
-            r.start();
+            if let Some(dir) = &config.record_dir {
+                if let Err(e) = r.enable_recording(std::path::Path::new(dir)) {
+                    eprintln!("⚠️  无法启用录音: {:#}", e);
+                }
+            }
         }
 
         let stream = recognizer.as_ref().map(|r| r.create_stream());
@@ -66,40 +73,72 @@ impl eframe::App for CinnabarWindow {
 
         // 处理语音识别
         let current_state = self.state_manager.lock().unwrap().get_state();
+        let listening = matches!(current_state, AppState::Listening | AppState::Recognizing);
+
+        if let (Some(ref mut recognizer), Some(ref mut stream)) =
+            (&mut self.recognizer, &mut self.stream)
+        {
+            // 设备状态在任何状态下都要检查，待机时拔出或重连设备也能及时显示
+            recognizer.poll_device();
+            let warning = match recognizer.device_status() {
+                DeviceStatus::Connected { .. } => None,
+                status => Some(status.to_string()),
+            };
+            self.state_manager
+                .lock()
+                .unwrap()
+                .set_device_warning(warning);
+
+            // 待机时也要读取采集缓冲区（只丢弃数据），否则约 2 秒后就会持续溢出
+            if listening {
+                recognizer.start();
+            } else {
+                recognizer.stop();
+                recognizer.process(stream, &mut Vec::new());
+            }
+        }
 
-        if current_state == AppState::Listening {
+        if listening {
             if let (Some(ref mut recognizer), Some(ref mut stream)) =
                 (&mut self.recognizer, &mut self.stream)
             {
-                if let Some(text) = recognizer.process(stream) {
+                let mut events = Vec::new();
+                recognizer.process(stream, &mut events);
+
+                let stats = recognizer.capture_stats();
+                let capture_warning = (stats.overruns > 0).then(|| stats.to_string());
+                self.state_manager
+                    .lock()
+                    .unwrap()
+                    .set_capture_warning(capture_warning);
+
+                // 分段器只输出新确认的文本：句末标点处确认完整的句子，断句时确认剩余部分
+                for event in events {
                     let state_manager = self.state_manager.lock().unwrap();
-                    state_manager.set_text(text.clone());
-                    state_manager.set_state(AppState::Recognizing);
-                    drop(state_manager);
-
-                    // 检测句子结束
-                    if text.ends_with('。')
-                        || text.ends_with('？')
-                        || text.ends_with('！')
-                        || text.ends_with('.')
-                        || text.ends_with('?')
-                        || text.ends_with('!')
-                    {
-                        self.state_manager
-                            .lock()
-                            .unwrap()
-                            .set_state(AppState::Injecting);
-
-                        // 注入文本
-                        if let Some(ref mut injector) = self.injector {
-                            let _ = injector.paste_text(&text);
+                    match event {
+                        SourceEvent::Partial(text) => {
+                            if !text.is_empty() {
+                                state_manager.set_state(AppState::Recognizing);
+                            }
+                            state_manager.set_text(text);
                         }
+                        SourceEvent::Final(text) => {
+                            state_manager.set_state(AppState::Injecting);
+
+                            // 注入文本
+                            if let Some(ref mut injector) = self.injector {
+                                let _ = injector.paste_text(&text);
+                            }
+                            state_manager.set_state(AppState::Recognizing);
+                        }
+                        SourceEvent::Endpoint(_) | SourceEvent::Error(_) => {}
+                    }
+                }
 
-                        // 重置 endpoint 检测器
-                        recognizer.reset_endpoint();
-
-                        // 返回待机状态
-                        let state_manager = self.state_manager.lock().unwrap();
+                // 一句话说完后返回待机状态
+                if recognizer.take_endpoint() {
+                    let state_manager = self.state_manager.lock().unwrap();
+                    if state_manager.get_state() == AppState::Recognizing {
                         state_manager.set_state(AppState::Idle);
                         state_manager.clear_text();
                     }
@@ -122,6 +161,34 @@ impl eframe::App for CinnabarWindow {
 
             ui.separator();
 
+            // 降噪 A/B 开关
+            if let Some(ref mut recognizer) = self.recognizer {
+                if recognizer.has_denoiser() {
+                    let mut enabled = !recognizer.is_denoise_bypassed();
+                    if ui.checkbox(&mut enabled, "降噪").changed() {
+                        recognizer.set_denoise_bypass(!enabled);
+                    }
+                }
+            }
+
+            // 设备重连提示
+            if let Some(warning) = state_manager.get_device_warning() {
+                ui.label(
+                    egui::RichText::new(format!("⚠️ {}", warning))
+                        .size(11.0)
+                        .color(egui::Color32::YELLOW),
+                );
+            }
+
+            // 采集溢出提示
+            if let Some(warning) = state_manager.get_capture_warning() {
+                ui.label(
+                    egui::RichText::new(format!("⚠️ {}", warning))
+                        .size(11.0)
+                        .color(egui::Color32::YELLOW),
+                );
+            }
+
             // 状态显示
             ui.vertical_centered(|ui| {
                 ui.label(
@@ -0,0 +1,248 @@
+use crate::audio::DeviceStatus;
+use crate::recorder::unix_seconds;
+use crate::segmenter::SourceEvent;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::SystemTime;
+
+/// 识别结果的输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 面向人阅读的文本（默认）
+    #[default]
+    Text,
+    /// 每个事件一行 JSON，便于脚本和编辑器插件解析
+    Jsonl,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "jsonl" => Ok(Self::Jsonl),
+            _ => anyhow::bail!("无效的输出格式: {}（可选 text、jsonl）", s),
+        }
+    }
+}
+
+/// 一行 JSON 记录，`type` 字段区分事件类型
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record<'a> {
+    Partial {
+        source: Option<&'a str>,
+        utterance: u64,
+        text: &'a str,
+        time: f64,
+    },
+    Final {
+        source: Option<&'a str>,
+        utterance: u64,
+        text: &'a str,
+        time: f64,
+    },
+    Endpoint {
+        source: Option<&'a str>,
+        utterance: u64,
+        text: &'a str,
+        tokens: &'a [String],
+        timestamps: &'a [f32],
+        start: f64,
+        end: f64,
+        reason: Option<&'a str>,
+        time: f64,
+    },
+    Device {
+        source: Option<&'a str>,
+        status: &'static str,
+        device: Option<&'a str>,
+        message: String,
+        time: f64,
+    },
+    Error {
+        source: Option<&'a str>,
+        message: &'a str,
+        time: f64,
+    },
+}
+
+/// JSON Lines 输出：每个事件一行 JSON 对象
+///
+/// 所有记录都带有来源标签（单一来源时为 `null`）和 Unix 时间戳 `time`（秒）；
+/// 识别事件带有语句序号 `utterance`，每路来源从 0 开始，断句后加一。
+pub struct JsonlWriter<W: Write> {
+    writer: W,
+    utterances: HashMap<Option<String>, u64>,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            utterances: HashMap::new(),
+        }
+    }
+
+    /// 输出一个识别事件
+    pub fn event(&mut self, source: Option<&str>, event: &SourceEvent) {
+        let key = source.map(str::to_string);
+        let utterance = self.utterances.get(&key).copied().unwrap_or(0);
+        let time = now();
+        let record = match event {
+            SourceEvent::Partial(text) => Record::Partial {
+                source,
+                utterance,
+                text,
+                time,
+            },
+            SourceEvent::Final(text) => Record::Final {
+                source,
+                utterance,
+                text,
+                time,
+            },
+            SourceEvent::Endpoint(u) => {
+                self.utterances.insert(key, utterance + 1);
+                Record::Endpoint {
+                    source,
+                    utterance,
+                    text: &u.text,
+                    tokens: &u.tokens,
+                    timestamps: &u.timestamps,
+                    start: u.start,
+                    end: u.end,
+                    reason: u.reason.as_deref(),
+                    time,
+                }
+            }
+            SourceEvent::Error(message) => Record::Error {
+                source,
+                message,
+                time,
+            },
+        };
+        self.write(&record);
+    }
+
+    /// 输出设备状态变化
+    pub fn device(&mut self, source: Option<&str>, status: &DeviceStatus) {
+        let (kind, device) = match status {
+            DeviceStatus::Connected { name } => ("connected", Some(name.as_str())),
+            DeviceStatus::Reconnecting { .. } => ("reconnecting", None),
+            DeviceStatus::Fallback { name } => ("fallback", Some(name.as_str())),
+        };
+        self.write(&Record::Device {
+            source,
+            status: kind,
+            device,
+            message: status.to_string(),
+            time: now(),
+        });
+    }
+
+    /// 输出错误
+    pub fn error(&mut self, source: Option<&str>, message: &str) {
+        self.write(&Record::Error {
+            source,
+            message,
+            time: now(),
+        });
+    }
+
+    fn write(&mut self, record: &Record) {
+        // 输出端关闭（如管道另一端退出）时无处报告，忽略写入错误
+        if let Ok(line) = serde_json::to_string(record) {
+            let _ = writeln!(self.writer, "{}", line);
+            let _ = self.writer.flush();
+        }
+    }
+}
+
+fn now() -> f64 {
+    unix_seconds(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segmenter::Utterance;
+
+    fn lines(writer: JsonlWriter<Vec<u8>>) -> Vec<serde_json::Value> {
+        String::from_utf8(writer.writer)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!(
+            "JSONL".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Jsonl
+        );
+        assert_eq!("text".parse::<OutputFormat>().unwrap(), OutputFormat::Text);
+        assert!("json".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_utterance_ids_per_source() {
+        let mut writer = JsonlWriter::new(Vec::new());
+        let utterance = Utterance {
+            text: "你好".to_string(),
+            tokens: vec!["你".to_string(), "好".to_string()],
+            timestamps: vec![0.4, 0.6],
+            start: 0.0,
+            end: 1.5,
+            ..Utterance::default()
+        };
+        writer.event(Some("me"), &SourceEvent::Partial("你".to_string()));
+        writer.event(Some("me"), &SourceEvent::Final("你好".to_string()));
+        writer.event(Some("me"), &SourceEvent::Endpoint(utterance));
+        writer.event(Some("remote"), &SourceEvent::Partial("hi".to_string()));
+        writer.event(Some("me"), &SourceEvent::Partial("再见".to_string()));
+
+        let lines = lines(writer);
+        let types: Vec<_> = lines.iter().map(|l| l["type"].as_str().unwrap()).collect();
+        assert_eq!(
+            types,
+            ["partial", "final", "endpoint", "partial", "partial"]
+        );
+        let ids: Vec<_> = lines
+            .iter()
+            .map(|l| l["utterance"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ids, [0, 0, 0, 0, 1]);
+
+        assert_eq!(lines[2]["tokens"][1], "好");
+        assert!((lines[2]["timestamps"][0].as_f64().unwrap() - 0.4).abs() < 1e-6);
+        assert_eq!(lines[2]["end"], 1.5);
+        assert!(lines[2]["reason"].is_null());
+        assert_eq!(lines[3]["source"], "remote");
+        assert!(lines[0]["time"].as_f64().unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_device_and_error_records() {
+        let mut writer = JsonlWriter::new(Vec::new());
+        writer.device(
+            None,
+            &DeviceStatus::Fallback {
+                name: "default".to_string(),
+            },
+        );
+        writer.error(None, "无法打开音频设备");
+
+        let lines = lines(writer);
+        assert_eq!(lines[0]["type"], "device");
+        assert_eq!(lines[0]["status"], "fallback");
+        assert_eq!(lines[0]["device"], "default");
+        assert!(lines[0]["source"].is_null());
+        assert_eq!(lines[1]["type"], "error");
+        assert_eq!(lines[1]["message"], "无法打开音频设备");
+    }
+}
@@ -1,156 +1,233 @@
+mod agc;
+mod audio;
 mod config;
+mod ctm;
+mod denoise;
+mod diagnostics;
+mod downmix;
+mod endpoint;
+mod eval;
+mod export;
 mod ffi;
+mod filter;
 mod gui;
 mod injector;
+mod jsonl;
+mod monitor;
+mod preprocess;
 mod recognizer;
+mod recorder;
+mod render;
 mod resampler;
+mod ringbuf;
+mod segmenter;
+mod source;
+mod subtitle;
+mod textgrid;
+mod transcriber;
 mod vad;
+mod wav;
 mod wayland;
 
-use anyhow::{Context, Result};
-use clap::Parser;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use crossbeam_channel::bounded;
+use anyhow::Result;
+use audio::{CaptureConfig, DeviceStatus};
+use clap::{Parser, Subcommand};
+use cpal::traits::{DeviceTrait, HostTrait};
 use ffi::OnlineRecognizer;
-use resampler::LinearResampler;
-use std::path::PathBuf;
+use jsonl::OutputFormat;
+use render::Output;
+use segmenter::SourceEvent;
+use source::{SourcePipeline, SourceSpec};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use vad::EndpointDetector;
+use std::time::Duration;
+
+/// 所有来源都没有新数据时的等待间隔
+const IDLE_SLEEP: Duration = Duration::from_millis(10);
 
 #[derive(Parser, Debug)]
 #[command(name = "cinnabar")]
 #[command(about = "轻量级、离线优先的 Linux 流式语音转文字工具")]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// 运行模式：cli 或 gui
     #[arg(short, long, default_value = "cli")]
     mode: String,
 
-    #[arg(short = 'M', long, default_value = "./models")]
+    #[arg(short = 'M', long, default_value = "./models", global = true)]
     model_dir: PathBuf,
 
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub config: Option<PathBuf>,
 
     #[arg(long)]
     list_devices: bool,
 
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     device: Option<usize>,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     device_name: Option<String>,
 
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// 音频后端（alsa、jack 或 pipewire），覆盖配置文件中的 `host`
+    #[arg(long, value_name = "HOST", global = true)]
+    host: Option<String>,
+
+    /// 采集来源：mic、loopback（系统音频）或 both，覆盖配置文件中的 `capture_source`
+    #[arg(long, value_name = "SOURCE")]
+    source: Option<source::CaptureSource>,
+
+    /// 同时采集的输入设备，格式为 `标签=设备名称`，可重复指定；覆盖配置文件中的 `[[sources]]`
+    #[arg(long = "input", value_name = "LABEL=DEVICE")]
+    inputs: Vec<source::SourceConfig>,
+
+    /// 将每个语句的音频（16 kHz WAV）和识别结果（JSON）保存到该目录
+    #[arg(long, value_name = "DIR")]
+    record: Option<PathBuf>,
+
+    /// 使用配置文件中 `[profiles.<NAME>]` 定义的配置档
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// 断句策略：energy、recognizer、neural 或 hybrid，覆盖配置文件中的 `endpoint_strategy`
+    #[arg(long, value_name = "STRATEGY", global = true)]
+    endpoint: Option<endpoint::EndpointStrategy>,
+
+    /// 输出格式：text（默认）或 jsonl（每个事件一行 JSON，便于脚本解析）
+    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
+    format: OutputFormat,
+
+    /// 旁路降噪阶段，用于对比降噪前后的识别效果
+    #[arg(long)]
+    bypass_denoise: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 录制几秒音频，报告输入电平、削波、噪声底和信噪比，并建议 vad_threshold
+    MicTest {
+        /// 录制时长（秒）
+        #[arg(long, default_value_t = 5)]
+        seconds: u64,
+    },
+    /// 测量几秒环境噪声，将噪声底加余量作为 vad_threshold 写入配置文件
+    Calibrate {
+        /// 录制时长（秒）
+        #[arg(long, default_value_t = 5)]
+        seconds: u64,
+        /// 阈值相对噪声底的余量（dB），默认使用配置中的 vad_margin_db
+        #[arg(long, value_name = "DB")]
+        margin_db: Option<f32>,
+        /// 只显示结果，不写入配置文件
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// 转写 WAV 文件，逐句输出识别结果
+    Transcribe {
+        /// 要转写的 WAV 文件（PCM 或 32 位浮点，任意采样率和声道数）
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// 同时导出为文件：srt、vtt（字幕）、ctm（词级时间和置信度）
+        /// 或 textgrid（Praat 标注），可重复指定
+        #[arg(long = "export", value_name = "FORMAT")]
+        exports: Vec<export::ExportFormat>,
+        /// 导出文件的目录，默认与音频文件相同
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+    },
+    /// 用带参考文本的音频评测识别准确率：中文按字（CER）、英文按词（WER）
+    Eval {
+        /// 评测清单：每行 `<WAV 路径><Tab><参考文本>`，相对路径相对于清单所在目录
+        manifest: PathBuf,
+    },
+}
+
+impl Args {
+    /// 加载配置文件，并应用配置档和命令行覆盖项
+    pub fn load_config(&self) -> Result<config::Config> {
+        let mut config = config::Config::load_or_default(self.config.as_deref())?;
+        config.apply_profile(self.profile.as_deref())?;
+        if let Some(host) = &self.host {
+            config.host = Some(host.clone());
+        }
+        if let Some(source) = self.source {
+            config.capture_source = source;
+        }
+        if !self.inputs.is_empty() {
+            config.sources = self.inputs.clone();
+        }
+        if let Some(dir) = &self.record {
+            config.record_dir = Some(dir.to_string_lossy().to_string());
+        }
+        if let Some(strategy) = self.endpoint {
+            config.endpoint_strategy = strategy;
+        }
+        if self.bypass_denoise {
+            config.denoise_bypass = true;
+        }
+        Ok(config)
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    let result = run(&args);
+    if let (Err(e), OutputFormat::Jsonl) = (&result, args.format) {
+        // 评测中失败的文件已逐条输出 `error` 记录
+        if !e.is::<eval::FailedFiles>() {
+            jsonl::JsonlWriter::new(std::io::stdout()).error(None, &format!("{:#}", e));
+        }
+    }
+    result
+}
+
+fn run(args: &Args) -> Result<()> {
+    if let Some(command) = &args.command {
+        return run_command(args, command);
+    }
+
     // 模式切换
     match args.mode.as_str() {
-        "gui" => return gui::run_gui_mode(&args),
+        "gui" => return gui::run_gui_mode(args),
         "cli" => {} // 继续执行 CLI 模式
         _ => anyhow::bail!("无效的模式。使用 'cli' 或 'gui'"),
     }
 
     // CLI 模式
-    let host = cpal::default_host();
+    let app_config = args.load_config()?;
 
     if args.list_devices {
-        println!("可用的音频输入设备：\n");
-        for (idx, device) in host.input_devices()?.enumerate() {
-            let name = device.name().unwrap_or_else(|_| "未知设备".to_string());
-            let config = device.default_input_config();
-            match config {
-                Ok(cfg) => println!(
-                    "  [{}] {} - {} Hz, {} 声道",
-                    idx,
-                    name,
-                    cfg.sample_rate().0,
-                    cfg.channels()
-                ),
-                Err(_) => println!("  [{}] {} - 无法获取配置", idx, name),
-            }
-        }
-        return Ok(());
+        return list_devices(&app_config);
     }
 
-    if !args.model_dir.exists() {
-        anyhow::bail!("未找到模型目录：{}", args.model_dir.display());
+    let recognizer = create_recognizer(&args.model_dir, &app_config)?;
+
+    let specs = SourceSpec::from_config(&app_config, args.device, args.device_name.clone())?;
+    // JSON Lines 格式下标准输出只包含事件，不输出启动信息
+    let text_output = args.format == OutputFormat::Text;
+    let mut pipelines = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let pipeline = SourcePipeline::open(spec, &app_config, &recognizer, args.verbose)?;
+        if text_output {
+            let prefix = source_prefix(pipeline.label());
+            let capture = pipeline.capture();
+            println!("🎧 {}音频后端: {}", prefix, capture.host_name());
+            println!("🎤 {}使用设备: {}", prefix, capture.device_name());
+            if let Some(source) = &capture.options().monitor {
+                println!("🔈 {}系统音频: {}", prefix, source);
+            }
+            print_capture_config(capture.config());
+        }
+        pipelines.push(pipeline);
     }
 
-    let recognizer = OnlineRecognizer::new(
-        &args.model_dir.join("encoder.int8.onnx").to_string_lossy(),
-        &args.model_dir.join("decoder.int8.onnx").to_string_lossy(),
-        &args.model_dir.join("tokens.txt").to_string_lossy(),
-        4,
-    )?;
-
-    let mut stream = recognizer.create_stream();
-
-    let device = if let Some(idx) = args.device {
-        host.input_devices()?
-            .nth(idx)
-            .context(format!("设备索引 {} 无效", idx))?
-    } else if let Some(name) = &args.device_name {
-        host.input_devices()?
-            .find(|d| d.name().ok().as_ref() == Some(name))
-            .context(format!("未找到设备名称: {}", name))?
-    } else {
-        host.default_input_device().context("未找到默认输入设备")?
-    };
-
-    println!(
-        "🎤 使用设备: {}",
-        device.name().unwrap_or_else(|_| "未知设备".to_string())
-    );
-
-    // 尝试配置 16000Hz 单声道，如果不支持则使用默认配置并启用重采样
-    let target_sample_rate = 16000;
-
-    // 检查设备是否支持 16kHz 单声道配置
-    let supports_16khz = device
-        .supported_input_configs()
-        .ok()
-        .and_then(|configs| {
-            configs.filter(|c| c.channels() == 1).find(|c| {
-                let min = c.min_sample_rate().0;
-                let max = c.max_sample_rate().0;
-                target_sample_rate >= min && target_sample_rate <= max
-            })
-        })
-        .is_some();
-
-    let (config, use_resampler) = if supports_16khz {
-        println!("🔧 使用配置: 16000 Hz, 1 声道");
-        (
-            cpal::StreamConfig {
-                channels: 1,
-                sample_rate: cpal::SampleRate(target_sample_rate),
-                buffer_size: cpal::BufferSize::Default,
-            },
-            false,
-        )
-    } else {
-        let default_config = device.default_input_config()?;
-        let sample_rate = default_config.sample_rate().0;
-        println!(
-            "⚠️  16kHz 不支持，使用默认配置: {} Hz, {} 声道（将启用重采样）",
-            sample_rate,
-            default_config.channels()
-        );
-        (
-            cpal::StreamConfig {
-                channels: default_config.channels(),
-                sample_rate: default_config.sample_rate(),
-                buffer_size: cpal::BufferSize::Default,
-            },
-            sample_rate != target_sample_rate,
-        )
-    };
-
     let running = Arc::new(AtomicBool::new(true));
     let running_clone = running.clone();
 
@@ -158,144 +235,361 @@ fn main() -> Result<()> {
         running_clone.store(false, Ordering::Relaxed);
     })?;
 
-    let (tx, rx) = bounded::<Vec<f32>>(100);
-    let actual_sample_rate = config.sample_rate.0;
-    let channels = config.channels;
-    let verbose = args.verbose;
+    if text_output {
+        println!("开始监听... 按 Ctrl+C 停止");
+
+        let preprocessor = pipelines[0].preprocessor();
+        if preprocessor.has_denoiser() {
+            println!(
+                "🔇 降噪: 强度 {:.2}{}",
+                app_config.denoise_strength,
+                if preprocessor.is_denoise_bypassed() {
+                    "（已旁路）"
+                } else {
+                    ""
+                }
+            );
+        }
+        if preprocessor.has_agc() {
+            println!(
+                "🔊 自动增益: 目标 {:.1} dBFS, 最大增益 {:.0} dB",
+                app_config.agc_target_dbfs, app_config.agc_max_gain_db
+            );
+        }
+    }
+
+    if let Some(dir) = app_config.record_dir.as_ref().map(PathBuf::from) {
+        if text_output {
+            println!("💾 录音目录: {}", dir.display());
+        }
+        for pipeline in &mut pipelines {
+            pipeline.enable_recording(&dir)?;
+        }
+    }
 
-    let audio_stream = device.build_input_stream(
-        &config,
-        move |data: &[f32], _| {
-            if verbose {
-                eprintln!("[DEBUG] 音频回调: 接收到 {} 个样本", data.len());
-            }
-            let mono_data: Vec<f32> = if channels > 1 {
-                data.chunks(channels as usize)
-                    .map(|chunk| {
-                        let sum: f32 = chunk.iter().sum();
-                        // 使用 sqrt(channels) 作为除数，避免音量过小
-                        sum / (channels as f32).sqrt()
-                    })
-                    .collect()
-            } else {
-                data.to_vec()
-            };
-            if verbose {
-                eprintln!("[DEBUG] 音频回调: 混音后 {} 个样本", mono_data.len());
+    let mut events = Vec::new();
+    // 调试输出和多路来源都会打乱原地刷新的部分结果行
+    let live = !args.verbose && !app_config.endpoint_log && pipelines.len() == 1;
+    let mut output = Output::new(args.format, live);
+    while running.load(Ordering::Relaxed) {
+        let mut received = false;
+
+        for pipeline in &mut pipelines {
+            if let Some(status) = pipeline.poll_device() {
+                if !output.device(pipeline.label(), &status) {
+                    let prefix = source_prefix(pipeline.label());
+                    match &status {
+                        DeviceStatus::Reconnecting { .. } => {
+                            println!("\n⚠️  {}{}", prefix, status)
+                        }
+                        DeviceStatus::Connected { .. } | DeviceStatus::Fallback { .. } => {
+                            println!("\n🔌 {}{}", prefix, status);
+                            print_capture_config(pipeline.capture().config());
+                        }
+                    }
+                }
             }
-            let _ = tx.try_send(mono_data);
-        },
-        |err| eprintln!("错误：{}", err),
-        None,
-    )?;
 
-    audio_stream.play()?;
+            received |= pipeline.step(&recognizer, &mut events);
+            output.events(pipeline.label(), &mut events);
+        }
 
-    println!("开始监听... 按 Ctrl+C 停止");
+        if !received {
+            std::thread::sleep(IDLE_SLEEP);
+        }
+    }
 
-    let mut resampler = if use_resampler {
-        Some(LinearResampler::new(actual_sample_rate, target_sample_rate))
-    } else {
-        None
-    };
+    output.clear();
+    for pipeline in &mut pipelines {
+        let stats = pipeline.capture().stats();
+        if stats.overruns > 0 || args.verbose {
+            let line = format!("📊 {}采集统计: {}", source_prefix(pipeline.label()), stats);
+            if text_output {
+                println!("{}", line);
+            } else {
+                eprintln!("{}", line);
+            }
+        }
+        // 退出前冲刷未结束的语句，输出并保存
+        pipeline.finish(&recognizer, &mut events);
+        output.events(pipeline.label(), &mut events);
+    }
 
-    let mut endpoint_detector = EndpointDetector::new(0.01, target_sample_rate, 1.2, 0.5);
-    let mut last_result = String::new();
-    let mut last_update_time = std::time::Instant::now();
+    Ok(())
+}
 
-    while running.load(Ordering::Relaxed) {
-        if let Ok(samples) = rx.recv_timeout(std::time::Duration::from_millis(100)) {
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 接收到 {} 个样本", samples.len());
-            }
-            if samples.is_empty() {
-                continue;
-            }
+fn run_command(args: &Args, command: &Command) -> Result<()> {
+    // 设备诊断只输出面向人阅读的报告，避免脚本把它当作 JSON 解析
+    if args.format == OutputFormat::Jsonl
+        && matches!(command, Command::MicTest { .. } | Command::Calibrate { .. })
+    {
+        anyhow::bail!("mic-test 和 calibrate 只输出文本报告，不支持 --format jsonl");
+    }
 
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 开始重采样");
+    let app_config = args.load_config()?;
+
+    let options = SourceSpec::from_config(&app_config, args.device, args.device_name.clone())?
+        .into_iter()
+        .next()
+        .map(|spec| spec.options)
+        .unwrap_or_default();
+
+    match command {
+        Command::MicTest { seconds } => {
+            println!(
+                "🎙️  录制 {} 秒，请正常说几句话，并在开头或结尾保持一两秒安静...",
+                seconds
+            );
+            let report =
+                diagnostics::run_mic_test(&app_config, options, Duration::from_secs(*seconds))?;
+            println!("\n{}", report);
+            for warning in report.warnings() {
+                println!("⚠️  {}", warning);
             }
-            let samples_16k = if let Some(ref mut r) = resampler {
-                r.resample(&samples)
-            } else {
-                samples
-            };
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 重采样后 {} 个样本", samples_16k.len());
+        }
+        Command::Calibrate {
+            seconds,
+            margin_db,
+            dry_run,
+        } => {
+            let margin_db = margin_db.unwrap_or(app_config.vad_margin_db);
+            println!("🤫 录制 {} 秒环境噪声，请保持安静...", seconds);
+            let calibration = diagnostics::run_calibration(
+                &app_config,
+                options,
+                Duration::from_secs(*seconds),
+                margin_db,
+            )?;
+            println!("\n{}", calibration);
+            for warning in calibration.warnings() {
+                println!("⚠️  {}", warning);
             }
-
-            // 检查重采样后的数据是否为空
-            if samples_16k.is_empty() {
-                continue;
+            if *dry_run {
+                return Ok(());
             }
 
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 调用 accept_waveform");
+            let path = args
+                .config
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(config::DEFAULT_CONFIG_PATH));
+            // 经字符串转换为 f64，避免 f32 直接转换带来的冗长小数
+            let threshold: f64 = format!("{:.5e}", calibration.threshold).parse()?;
+            let margin_db: f64 = margin_db.to_string().parse()?;
+            config::Config::save_values(
+                &path,
+                &[
+                    ("vad_threshold", threshold.into()),
+                    ("vad_margin_db", margin_db.into()),
+                ],
+            )?;
+            println!("💾 已写入 {}", path.display());
+        }
+        Command::Transcribe {
+            files,
+            exports,
+            output_dir,
+        } => {
+            for format in exports {
+                export::check_export_paths(*format, files, output_dir.as_deref())?;
             }
-            stream.accept_waveform(target_sample_rate as i32, &samples_16k);
-
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 检查 is_ready");
+            let recognizer = create_recognizer(&args.model_dir, &app_config)?;
+            let mut writer = jsonl::JsonlWriter::new(std::io::stdout());
+            for file in files {
+                let transcript =
+                    transcriber::transcribe_file(&app_config, &recognizer, file, args.verbose)?;
+                for format in exports {
+                    let path = export::write_export(
+                        *format,
+                        &transcript,
+                        &app_config,
+                        file,
+                        output_dir.as_deref(),
+                    )?;
+                    eprintln!("💾 已写入 {}", path.display());
+                }
+                let name = file.display().to_string();
+                if args.format == OutputFormat::Jsonl {
+                    for utterance in transcript.utterances {
+                        writer.event(Some(&name), &SourceEvent::Endpoint(utterance));
+                    }
+                    continue;
+                }
+                // 多个文件时每句前标注文件名
+                let prefix = if files.len() > 1 {
+                    source_prefix(Some(&name))
+                } else {
+                    String::new()
+                };
+                for utterance in transcript.utterances {
+                    println!("{}{}", prefix, utterance.text);
+                }
             }
-            while recognizer.is_ready(&stream) {
-                if args.verbose {
-                    eprintln!("[DEBUG] 主循环: 调用 decode");
+        }
+        Command::Eval { manifest } => {
+            let entries = eval::read_manifest(manifest)?;
+            let recognizer = create_recognizer(&args.model_dir, &app_config)?;
+            let jsonl = args.format == OutputFormat::Jsonl;
+            let mut summary = eval::Summary::default();
+            for entry in &entries {
+                // 与 transcribe 相同的完整处理链：预处理、重采样、断句、识别；
+                // 单个文件失败时记录下来并继续评测其余文件
+                let transcript = match transcriber::transcribe_file(
+                    &app_config,
+                    &recognizer,
+                    &entry.audio,
+                    args.verbose,
+                ) {
+                    Ok(transcript) => transcript,
+                    Err(e) => {
+                        summary.add_failure();
+                        if jsonl {
+                            let record = eval::Record::error(&entry.audio, &e);
+                            println!("{}", serde_json::to_string(&record)?);
+                        } else {
+                            println!("== {}  转写失败: {:#}\n", entry.audio.display(), e);
+                        }
+                        continue;
+                    }
+                };
+                let hypothesis: Vec<&str> = transcript
+                    .utterances
+                    .iter()
+                    .map(|u| u.text.as_str())
+                    .collect();
+                let result =
+                    eval::FileResult::new(&entry.audio, &entry.reference, &hypothesis.join(" "));
+                summary.add(&result);
+                if jsonl {
+                    println!("{}", serde_json::to_string(&eval::Record::file(&result))?);
+                    continue;
                 }
-                recognizer.decode(&mut stream);
+                println!(
+                    "== {}  {} {:.2}%（{}）",
+                    entry.audio.display(),
+                    result.metric,
+                    result.counts.rate() * 100.0,
+                    result.counts
+                );
+                println!("{}\n", result.alignment());
             }
 
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 获取结果");
+            for &(metric, files, counts) in summary.totals() {
+                if jsonl {
+                    let record = eval::Record::summary(metric, files, counts);
+                    println!("{}", serde_json::to_string(&record)?);
+                } else {
+                    println!(
+                        "📊 {} {:.2}%（{}），共 {} 个文件",
+                        metric,
+                        counts.rate() * 100.0,
+                        counts,
+                        files
+                    );
+                }
             }
-            let result = recognizer.get_result(&stream);
-            let trimmed = result.trim();
-
-            if !trimmed.is_empty() && trimmed != last_result {
-                last_result = trimmed.to_string();
-                last_update_time = std::time::Instant::now();
+            if summary.failed() > 0 {
+                return Err(eval::FailedFiles {
+                    failed: summary.failed(),
+                    total: entries.len(),
+                }
+                .into());
             }
+        }
+    }
 
-            // 如果超过 500ms 没有新内容，输出当前结果
-            if !last_result.is_empty() && last_update_time.elapsed().as_millis() > 500 {
-                println!("{}", last_result);
-                last_result.clear();
-            }
+    Ok(())
+}
 
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 检查 endpoint");
-            }
-            let is_endpoint = endpoint_detector.accept_waveform(&samples_16k);
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: endpoint = {}", is_endpoint);
+fn create_recognizer(model_dir: &Path, config: &config::Config) -> Result<OnlineRecognizer> {
+    if !model_dir.exists() {
+        anyhow::bail!("未找到模型目录：{}", model_dir.display());
+    }
+
+    OnlineRecognizer::new(
+        &model_dir.join("encoder.int8.onnx").to_string_lossy(),
+        &model_dir.join("decoder.int8.onnx").to_string_lossy(),
+        &model_dir.join("tokens.txt").to_string_lossy(),
+        4,
+        endpoint::recognizer_rules(config),
+    )
+}
+
+/// 多路来源时输出的标签前缀
+fn source_prefix(label: Option<&str>) -> String {
+    label.map(|l| format!("[{}] ", l)).unwrap_or_default()
+}
+
+/// 列出所有可用音频后端的输入设备；设备索引在各后端内独立编号
+fn list_devices(config: &config::Config) -> Result<()> {
+    let selected = audio::select_host(config.host.as_deref())?.id();
+
+    for host_id in cpal::available_hosts() {
+        let marker = if host_id == selected {
+            "（当前）"
+        } else {
+            ""
+        };
+        println!("{}{}:", host_id.name(), marker);
+
+        let devices = cpal::host_from_id(host_id)
+            .map_err(anyhow::Error::from)
+            .and_then(|host| Ok(host.input_devices()?));
+        let devices = match devices {
+            Ok(devices) => devices,
+            Err(e) => {
+                println!("  无法枚举设备: {}\n", e);
+                continue;
             }
-            if is_endpoint {
-                if args.verbose {
-                    eprintln!("[DEBUG] 主循环: endpoint 为 true，获取最终结果");
-                }
-                let final_result = recognizer.get_result(&stream);
-                if args.verbose {
-                    eprintln!(
-                        "[DEBUG] 主循环: 获取到最终结果，长度 = {}",
-                        final_result.len()
-                    );
-                }
-                if !final_result.trim().is_empty() {
-                    println!("\n✅ {}", final_result.trim());
-                }
-                if args.verbose {
-                    eprintln!("[DEBUG] 主循环: 准备重置流和检测器");
-                }
-                recognizer.reset(&mut stream);
-                endpoint_detector.reset();
-                if args.verbose {
-                    eprintln!("[DEBUG] 主循环: 流和检测器已重置");
-                }
+        };
+
+        for (idx, device) in devices.enumerate() {
+            let name = device.name().unwrap_or_else(|_| "未知设备".to_string());
+            match audio::negotiate_config(&device, 1) {
+                Ok(capture) => println!(
+                    "  [{}] {} - {} Hz, {} 声道, {:?}",
+                    idx,
+                    name,
+                    capture.sample_rate(),
+                    capture.channels(),
+                    capture.sample_format
+                ),
+                Err(_) => println!("  [{}] {} - 无法获取配置", idx, name),
             }
-            if args.verbose {
-                eprintln!("[DEBUG] 主循环: 本次循环结束");
+        }
+        println!();
+    }
+
+    // 系统音频监视源由 PulseAudio / PipeWire 提供，不在 ALSA 设备列表中
+    match monitor::list_monitor_sources() {
+        Ok(sources) if !sources.is_empty() => {
+            println!("系统音频监视源（capture_source = \"loopback\" 或 \"both\"）:");
+            for source in sources {
+                println!("  {}", source);
             }
+            println!();
         }
+        Ok(_) => {}
+        Err(e) => println!("无法列出系统音频监视源: {:#}\n", e),
     }
 
+    println!("使用 --host <名称> 选择后端，--device / --device-name 在所选后端内选择设备");
     Ok(())
 }
+
+fn print_capture_config(capture: &CaptureConfig) {
+    if capture.needs_resampling() {
+        println!(
+            "⚠️  16kHz 不支持，使用默认配置: {} Hz, {} 声道, {:?}（将启用重采样）",
+            capture.sample_rate(),
+            capture.channels(),
+            capture.sample_format
+        );
+    } else {
+        println!(
+            "🔧 使用配置: {} Hz, {} 声道, {:?}",
+            capture.sample_rate(),
+            capture.channels(),
+            capture.sample_format
+        );
+    }
+}
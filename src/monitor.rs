@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait};
+use std::process::Command;
+
+/// PulseAudio 协议中表示“默认输出设备的监视源”的特殊名称（pipewire-pulse 同样支持）
+pub const DEFAULT_MONITOR: &str = "@DEFAULT_MONITOR@";
+
+/// 通过这些 ALSA 设备访问 PulseAudio / PipeWire，按顺序尝试
+const SOUND_SERVER_PCMS: [&str; 2] = ["pulse", "pipewire"];
+
+/// 将配置中的监视源名称转换为声音服务器使用的名称
+///
+/// `default` 表示默认输出设备的监视源，其他名称原样使用
+/// （例如 `alsa_output.pci-0000_00_1f.3.analog-stereo.monitor`）。
+pub fn resolve_source(name: &str) -> &str {
+    if name.is_empty() || name.eq_ignore_ascii_case("default") {
+        DEFAULT_MONITOR
+    } else {
+        name
+    }
+}
+
+/// 查找通往声音服务器的 ALSA 设备（`pulse` 或 `pipewire`）
+pub fn sound_server_device(host: &cpal::Host) -> Result<cpal::Device> {
+    let devices: Vec<cpal::Device> = host.input_devices()?.collect();
+    SOUND_SERVER_PCMS
+        .iter()
+        .find_map(|pcm| {
+            devices
+                .iter()
+                .find(|d| d.name().ok().as_deref() == Some(*pcm))
+                .cloned()
+        })
+        .context("未找到 pulse 或 pipewire 设备，无法采集系统音频（需要 PulseAudio 或 PipeWire）")
+}
+
+/// 打开流后把本进程新建的录音流移到监视源 `source`
+///
+/// 通过声音服务器移动录音流（`pactl move-source-output`），不修改进程环境变量，
+/// 因此不会与已在运行的音频线程竞争，同一进程中的麦克风流也不受影响。
+/// 调用方需保证同一时刻只有一处在打开流，否则可能移动到其他新建的流。
+pub fn open_on_source<T>(source: &str, open: impl FnOnce() -> Result<T>) -> Result<T> {
+    let before = own_source_outputs()?;
+    let stream = open()?;
+    let created: Vec<u32> = own_source_outputs()?
+        .into_iter()
+        .filter(|index| !before.contains(index))
+        .collect();
+    if created.is_empty() {
+        anyhow::bail!("未找到新建的录音流，无法切换到监视源 {}", source);
+    }
+    for index in created {
+        pactl(&["move-source-output", &index.to_string(), source])
+            .with_context(|| format!("无法切换到监视源 {}", source))?;
+    }
+    Ok(stream)
+}
+
+/// 本进程当前的录音流编号
+fn own_source_outputs() -> Result<Vec<u32>> {
+    let output = pactl(&["list", "source-outputs"])?;
+    Ok(parse_source_outputs(&output, std::process::id()))
+}
+
+/// 运行 `pactl` 并返回标准输出
+fn pactl(args: &[&str]) -> Result<String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .output()
+        .context("无法运行 pactl")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "pactl 执行失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 通过 `pactl` 列出可用的监视源
+pub fn list_monitor_sources() -> Result<Vec<String>> {
+    Ok(parse_monitor_sources(&pactl(&[
+        "list", "short", "sources",
+    ])?))
+}
+
+/// 解析 `pactl list short sources` 的输出，返回监视源名称
+fn parse_monitor_sources(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1))
+        .filter(|name| name.ends_with(".monitor"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 解析 `pactl list source-outputs` 的输出，返回属于进程 `pid` 的录音流编号
+fn parse_source_outputs(output: &str, pid: u32) -> Vec<u32> {
+    let pid = format!("application.process.id = \"{}\"", pid);
+    let mut indices = Vec::new();
+    let mut current: Option<u32> = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(index) = line.strip_prefix("Source Output #") {
+            current = index.trim().parse().ok();
+        } else if line == pid {
+            indices.extend(current.take());
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_source() {
+        assert_eq!(resolve_source("default"), DEFAULT_MONITOR);
+        assert_eq!(resolve_source(""), DEFAULT_MONITOR);
+        assert_eq!(resolve_source("speaker.monitor"), "speaker.monitor");
+    }
+
+    #[test]
+    fn test_parse_monitor_sources() {
+        let output = "\
+50\talsa_output.pci-0000_00_1f.3.analog-stereo.monitor\tPipeWire\ts32le 2ch 48000Hz\tSUSPENDED
+51\talsa_input.pci-0000_00_1f.3.analog-stereo\tPipeWire\ts32le 2ch 48000Hz\tRUNNING
+52\tbluez_output.00_11_22.1.monitor\tPipeWire\ts16le 2ch 48000Hz\tIDLE
+";
+        assert_eq!(
+            parse_monitor_sources(output),
+            vec![
+                "alsa_output.pci-0000_00_1f.3.analog-stereo.monitor",
+                "bluez_output.00_11_22.1.monitor"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_source_outputs() {
+        let output = "\
+Source Output #12
+\tDriver: protocol-native.c
+\tProperties:
+\t\tapplication.name = \"ALSA plug-in [cinnabar]\"
+\t\tapplication.process.id = \"4321\"
+
+Source Output #13
+\tDriver: protocol-native.c
+\tProperties:
+\t\tapplication.name = \"Firefox\"
+\t\tapplication.process.id = \"999\"
+
+Source Output #17
+\tProperties:
+\t\tapplication.process.id = \"4321\"
+";
+        assert_eq!(parse_source_outputs(output, 4321), vec![12, 17]);
+        assert!(parse_source_outputs(output, 1).is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+use crate::agc::Agc;
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::Config;
+use crate::denoise::Denoiser;
+use crate::filter::HighPassFilter;
+
+/// 采集后的预处理链
+///
+/// 高通和降噪位于声道混音之后、VAD 与重采样之前，按采集采样率处理单声道音频。
+/// AGC 单独由 [`Preprocessor::apply_gain`] 执行：它作用于重采样后的 16 kHz 音频，
+/// 位于 VAD 和识别器之前，增益调整由上一帧的 VAD 判定控制。
+pub struct Preprocessor {
+    highpass: Option<HighPassFilter>,
+    denoiser: Option<Denoiser>,
+    agc: Option<Agc>,
+}
+
+impl Preprocessor {
+    pub fn new(config: &Config, sample_rate: u32) -> Self {
+        let highpass = (config.highpass_hz > 0.0)
+            .then(|| HighPassFilter::new(sample_rate, config.highpass_hz));
+        let denoiser = config.denoise.then(|| {
+            let mut denoiser = Denoiser::new(sample_rate, config.denoise_strength);
+            denoiser.set_bypass(config.denoise_bypass);
+            denoiser
+        });
+
+        let agc = config
+            .agc
+            .then(|| Agc::new(TARGET_SAMPLE_RATE, config.agc_params()));
+
+        Self {
+            highpass,
+            denoiser,
+            agc,
+        }
+    }
+
+    /// 依次执行 VAD 之前的处理阶段（高通 → 降噪）；没有启用任何阶段时原样返回
+    pub fn process(&mut self, mut samples: Vec<f32>) -> Vec<f32> {
+        if let Some(ref mut highpass) = self.highpass {
+            highpass.process(&mut samples);
+        }
+        match self.denoiser {
+            Some(ref mut denoiser) => denoiser.process(&samples),
+            None => samples,
+        }
+    }
+
+    /// 对送入 VAD 之前的 16 kHz 音频执行 AGC；`speech` 为上一帧的 VAD 判定。
+    /// 未启用时不做处理
+    pub fn apply_gain(&mut self, samples: &mut [f32], speech: bool) {
+        if let Some(ref mut agc) = self.agc {
+            agc.set_speech(speech);
+            agc.process(samples);
+        }
+    }
+
+    pub fn has_agc(&self) -> bool {
+        self.agc.is_some()
+    }
+
+    pub fn has_denoiser(&self) -> bool {
+        self.denoiser.is_some()
+    }
+
+    pub fn is_denoise_bypassed(&self) -> bool {
+        self.denoiser.as_ref().is_some_and(|d| d.is_bypassed())
+    }
+
+    /// 旁路降噪，用于对比降噪前后的识别效果
+    pub fn set_denoise_bypass(&mut self, bypass: bool) {
+        if let Some(ref mut denoiser) = self.denoiser {
+            denoiser.set_bypass(bypass);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passthrough_without_stages() {
+        let config = Config {
+            highpass_hz: 0.0,
+            ..Config::default()
+        };
+        let mut preprocessor = Preprocessor::new(&config, 16000);
+        assert!(!preprocessor.has_denoiser());
+        assert!(!preprocessor.has_agc());
+        assert_eq!(preprocessor.process(vec![0.1, 0.2]), vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_denoise_bypass_toggle() {
+        let config = Config {
+            highpass_hz: 0.0,
+            denoise: true,
+            ..Config::default()
+        };
+        let mut preprocessor = Preprocessor::new(&config, 16000);
+        assert!(preprocessor.has_denoiser());
+        assert!(!preprocessor.is_denoise_bypassed());
+
+        preprocessor.set_denoise_bypass(true);
+        assert!(preprocessor.is_denoise_bypassed());
+        // 旁路时保持与降噪相同的延迟
+        let output = preprocessor.process(vec![0.1; 1600]);
+        assert!(output.len() < 1600);
+        assert!(output.iter().skip(512).all(|s| (s - 0.1).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_agc_stage() {
+        let config = Config {
+            highpass_hz: 0.0,
+            agc: true,
+            ..Config::default()
+        };
+        let mut preprocessor = Preprocessor::new(&config, 16000);
+        assert!(preprocessor.has_agc());
+
+        let mut samples: Vec<f32> = (0..56000)
+            .map(|i| (i as f32 * 0.1).sin() * if i < 8000 { 0.0003 } else { 0.01 })
+            .collect();
+        // 高通、降噪阶段不改变电平
+        let output = preprocessor.process(samples.clone());
+        assert_eq!(output, samples);
+
+        preprocessor.apply_gain(&mut samples, false);
+        let peak = samples[40000..].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.1);
+    }
+
+    #[test]
+    fn test_highpass_runs_first() {
+        let mut preprocessor = Preprocessor::new(&Config::default(), 16000);
+        let output = preprocessor.process(vec![0.2; 16000]);
+        assert!(output[8000..].iter().all(|s| s.abs() < 1e-4));
+    }
+}
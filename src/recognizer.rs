@@ -1,185 +1,263 @@
+use crate::audio::{AudioCapture, CaptureStats, DeviceStatus, TARGET_SAMPLE_RATE};
+use crate::config::Config;
+use crate::endpoint::{self, Endpointer};
 use crate::ffi::OnlineRecognizer;
-use crate::resampler::LinearResampler;
-use crate::vad::{EndpointDetector, VadDetector};
-use anyhow::{Context, Result};
-use cpal::traits::{DeviceTrait, HostTrait};
-use crossbeam_channel::{bounded, Receiver};
+use crate::preprocess::Preprocessor;
+use crate::recorder::UtteranceRecorder;
+use crate::resampler::Resampler;
+use crate::segmenter::{Segmenter, SourceEvent};
+use crate::source::SourceSpec;
+use crate::vad::{FrameVad, SpeechGate};
+use anyhow::Result;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 pub struct RecognizerEngine {
     recognizer: OnlineRecognizer,
-    _stream: cpal::Stream,
-    rx: Receiver<Vec<f32>>,
+    capture: AudioCapture,
     running: Arc<AtomicBool>,
-    resampler: Option<LinearResampler>,
+    config: Config,
+    preprocessor: Preprocessor,
+    resampler: Option<Resampler>,
     target_sample_rate: u32,
-    vad: VadDetector,
-    endpoint_detector: EndpointDetector,
+    gate: SpeechGate,
+    /// 对重采样后、未经门限的 16 kHz 音频做断句，静音期间也能计时
+    endpointer: Endpointer,
+    endpoint_pending: bool,
+    segmenter: Segmenter,
+    recorder: Option<UtteranceRecorder>,
 }
 
 impl RecognizerEngine {
     pub fn new(
-        model_dir: &std::path::Path,
+        config: &Config,
         device_idx: Option<usize>,
         device_name: Option<String>,
     ) -> Result<Self> {
+        let model_dir = std::path::Path::new(&config.model_dir);
         let recognizer = OnlineRecognizer::new(
             &model_dir.join("encoder.int8.onnx").to_string_lossy(),
             &model_dir.join("decoder.int8.onnx").to_string_lossy(),
             &model_dir.join("tokens.txt").to_string_lossy(),
             4,
+            endpoint::recognizer_rules(config),
         )?;
 
-        let host = cpal::default_host();
-        let device = if let Some(idx) = device_idx {
-            host.input_devices()?
-                .nth(idx)
-                .context(format!("设备索引 {} 无效", idx))?
-        } else if let Some(name) = &device_name {
-            host.input_devices()?
-                .find(|d| d.name().ok().as_ref() == Some(name))
-                .context(format!("未找到设备名称: {}", name))?
-        } else {
-            host.default_input_device().context("未找到默认输入设备")?
-        };
-
-        let target_sample_rate = 16000;
-        let supports_16khz = device
-            .supported_input_configs()
-            .ok()
-            .and_then(|configs| {
-                configs.filter(|c| c.channels() == 1).find(|c| {
-                    let min = c.min_sample_rate().0;
-                    let max = c.max_sample_rate().0;
-                    target_sample_rate >= min && target_sample_rate <= max
-                })
-            })
-            .is_some();
-
-        let (config, use_resampler) = if supports_16khz {
-            (
-                cpal::StreamConfig {
-                    channels: 1,
-                    sample_rate: cpal::SampleRate(target_sample_rate),
-                    buffer_size: cpal::BufferSize::Default,
-                },
-                false,
-            )
-        } else {
-            let default_config = device.default_input_config()?;
-            (
-                cpal::StreamConfig {
-                    channels: default_config.channels(),
-                    sample_rate: default_config.sample_rate(),
-                    buffer_size: cpal::BufferSize::Default,
-                },
-                default_config.sample_rate().0 != target_sample_rate,
-            )
-        };
-
-        let (tx, rx) = bounded::<Vec<f32>>(100);
-        let channels = config.channels;
-
-        let stream = device.build_input_stream(
-            &config,
-            move |data: &[f32], _| {
-                let mono_data: Vec<f32> = if channels > 1 {
-                    data.chunks(channels as usize)
-                        .map(|chunk| chunk.iter().sum::<f32>() / (channels as f32).sqrt())
-                        .collect()
-                } else {
-                    data.to_vec()
-                };
-                let _ = tx.try_send(mono_data);
-            },
-            |err| eprintln!("错误：{}", err),
-            None,
-        )?;
+        // GUI 只显示一路识别结果：多路来源时使用第一路
+        let options = SourceSpec::from_config(config, device_idx, device_name)?
+            .into_iter()
+            .next()
+            .map(|spec| spec.options)
+            .unwrap_or_default();
+        let capture = AudioCapture::open(options)?;
 
-        let resampler = if use_resampler {
-            Some(LinearResampler::new(
-                config.sample_rate.0,
-                target_sample_rate,
-            ))
-        } else {
-            None
-        };
+        let target_sample_rate = TARGET_SAMPLE_RATE;
+        let resampler = Resampler::new_if_needed(
+            config.resampler,
+            capture.config().sample_rate(),
+            target_sample_rate,
+        );
+        let preprocessor = Preprocessor::new(config, capture.config().sample_rate());
+        // VAD 与 CLI 一样运行在重采样后的 16 kHz 音频上，`cinnabar calibrate` 的阈值可以直接使用
+        let gate = SpeechGate::new(
+            FrameVad::from_config(config, target_sample_rate),
+            config.pre_roll_ms,
+        );
+        let endpointer = Self::create_endpointer(config, target_sample_rate)?;
 
         Ok(Self {
             recognizer,
-            _stream: stream,
-            rx,
+            capture,
             running: Arc::new(AtomicBool::new(false)),
+            config: config.clone(),
+            preprocessor,
             resampler,
             target_sample_rate,
-            vad: VadDetector::new(0.01),
-            endpoint_detector: EndpointDetector::new(0.01, target_sample_rate, 1.2, 0.5),
+            gate,
+            endpointer,
+            endpoint_pending: false,
+            segmenter: Segmenter::new(),
+            recorder: None,
         })
     }
 
+    /// 启用语句录音，音频和元数据写入 `dir`
+    pub fn enable_recording(&mut self, dir: &std::path::Path) -> Result<()> {
+        self.recorder = Some(UtteranceRecorder::new(
+            dir,
+            self.capture.device_name(),
+            self.target_sample_rate,
+        )?);
+        Ok(())
+    }
+
     pub fn start(&mut self) {
         self.running.store(true, Ordering::Relaxed);
     }
 
-    #[allow(dead_code)]
+    /// 暂停识别；之后 [`RecognizerEngine::process`] 只丢弃采集到的音频
     pub fn stop(&mut self) {
         self.running.store(false, Ordering::Relaxed);
     }
 
-    pub fn process(&mut self, stream: &mut crate::ffi::OnlineStream) -> Option<String> {
-        if !self.running.load(Ordering::Relaxed) {
-            return None;
-        }
+    /// 当前音频设备状态
+    pub fn device_status(&self) -> &DeviceStatus {
+        self.capture.status()
+    }
 
-        if let Ok(samples) = self.rx.try_recv() {
-            if samples.is_empty() {
-                return None;
-            }
+    /// 采集统计（缓冲区溢出等）
+    pub fn capture_stats(&self) -> CaptureStats {
+        self.capture.stats()
+    }
 
-            // VAD 检测
-            if !self.vad.is_speech(&samples) {
-                return None;
-            }
+    /// 是否启用了降噪阶段
+    pub fn has_denoiser(&self) -> bool {
+        self.preprocessor.has_denoiser()
+    }
+
+    pub fn is_denoise_bypassed(&self) -> bool {
+        self.preprocessor.is_denoise_bypassed()
+    }
 
-            let resampled = if let Some(ref mut r) = self.resampler {
-                r.resample(&samples)
-            } else {
-                samples
-            };
+    /// 旁路降噪，用于 A/B 对比
+    pub fn set_denoise_bypass(&mut self, bypass: bool) {
+        self.config.denoise_bypass = bypass;
+        self.preprocessor.set_denoise_bypass(bypass);
+    }
 
-            if resampled.is_empty() {
-                return None;
+    /// 检查设备状态；切换到新的流后按新设备的采样率重建预处理链和重采样器
+    ///
+    /// VAD 和断句检测器运行在固定的 16 kHz 上，不受设备采样率影响。
+    /// GUI 每一帧都会调用，与是否正在识别无关。
+    pub fn poll_device(&mut self) {
+        if let Some(status) = self.capture.poll() {
+            eprintln!("🔌 {}", status);
+        }
+        if self.capture.take_reopened() {
+            let sample_rate = self.capture.config().sample_rate();
+            self.preprocessor = Preprocessor::new(&self.config, sample_rate);
+            self.resampler = Resampler::new_if_needed(
+                self.config.resampler,
+                sample_rate,
+                self.target_sample_rate,
+            );
+            if let Some(ref mut recorder) = self.recorder {
+                recorder.set_device_name(self.capture.device_name());
             }
+        }
+    }
 
-            stream.accept_waveform(self.target_sample_rate as i32, &resampled);
+    /// 处理已采集的音频，新确认的文本和部分结果追加到 `events`
+    ///
+    /// 断句时输出剩余文本并重置识别流，之后 `take_endpoint` 返回 `true`。
+    pub fn process(
+        &mut self,
+        stream: &mut crate::ffi::OnlineStream,
+        events: &mut Vec<SourceEvent>,
+    ) {
+        // 未运行时丢弃采集到的音频：环形缓冲区不会溢出，开始识别时也不会送入过时的音频
+        if !self.running.load(Ordering::Relaxed) {
+            self.capture.discard();
+            return;
+        }
 
-            while self.recognizer.is_ready(stream) {
-                self.recognizer.decode(stream);
-            }
+        let Some(samples) = self.capture.try_recv() else {
+            return;
+        };
+        let samples = self.preprocessor.process(samples);
+        let mut samples = match self.resampler {
+            Some(ref mut r) => r.resample(&samples),
+            None => samples,
+        };
+        if samples.is_empty() {
+            return;
+        }
 
-            let result = self.recognizer.get_result(stream);
-            let trimmed = result.trim();
+        // 录音保存 AGC 之前、未经门限的音频，与 CLI 相同，可直接用 `cinnabar transcribe` 回放
+        let ungained = self.recorder.as_ref().map(|_| samples.clone());
+        // AGC 位于 VAD 之前，按上一帧的 VAD 判定决定是否调整增益
+        self.preprocessor
+            .apply_gain(&mut samples, self.endpointer.is_speech());
+        self.endpointer.accept_waveform(&samples);
+        if let (Some(recorder), Some(ungained)) = (&mut self.recorder, ungained) {
+            recorder.push(&ungained, self.endpointer.utterance_seconds() > 0.0);
+        }
+        if let Some(result) = self.decode(stream, samples) {
+            self.segmenter.update(&result, events);
+        }
 
-            if !trimmed.is_empty() {
-                return Some(trimmed.to_string());
-            }
+        let recognizer = &self.recognizer;
+        if self
+            .endpointer
+            .check(|| recognizer.is_endpoint(stream))
+            .is_some()
+        {
+            self.endpoint_pending = true;
+            self.finish_utterance(stream, events);
+        }
+    }
+
+    /// 经过 AGC 的 16 kHz 音频经门限后送入识别器解码，返回当前识别结果
+    fn decode(
+        &mut self,
+        stream: &mut crate::ffi::OnlineStream,
+        samples: Vec<f32>,
+    ) -> Option<String> {
+        // VAD 门限：静音进入预录缓冲，语音开始时连同缓冲一起送入；
+        // 使用识别器断句规则时识别器需要看到静音，不经过门限
+        let samples = if self.endpointer.strategy().uses_recognizer() {
+            samples
+        } else {
+            self.gate.process(&samples)
+        };
+        if samples.is_empty() {
+            return None;
+        }
+
+        stream.accept_waveform(self.target_sample_rate as i32, &samples);
+
+        while self.recognizer.is_ready(stream) {
+            self.recognizer.decode(stream);
         }
 
-        None
+        let result = self.recognizer.get_result(stream);
+        let trimmed = result.trim();
+        (!trimmed.is_empty()).then(|| trimmed.to_string())
     }
 
     pub fn create_stream(&self) -> crate::ffi::OnlineStream {
         self.recognizer.create_stream()
     }
 
-    #[allow(dead_code)]
-    pub fn is_endpoint(&mut self, samples: &[f32]) -> bool {
-        self.endpoint_detector.accept_waveform(samples)
+    fn create_endpointer(config: &Config, sample_rate: u32) -> Result<Endpointer> {
+        let mut endpointer = Endpointer::from_config(config, sample_rate)?;
+        if config.endpoint_log {
+            endpointer.enable_log("GUI");
+        }
+        Ok(endpointer)
+    }
+
+    /// 自上次调用以来是否检测到断句（静音或达到最长语句时长），
+    /// 此时该句的文本已全部确认
+    pub fn take_endpoint(&mut self) -> bool {
+        std::mem::take(&mut self.endpoint_pending)
     }
 
-    #[allow(dead_code)]
-    pub fn reset_endpoint(&mut self) {
-        self.endpoint_detector.reset();
+    /// 结束当前语句：确认剩余文本，保存录音（如已启用），重置断句检测器和识别流
+    fn finish_utterance(
+        &mut self,
+        stream: &mut crate::ffi::OnlineStream,
+        events: &mut Vec<SourceEvent>,
+    ) {
+        let result = self.recognizer.get_result(stream);
+        let text = result.trim();
+        self.segmenter.end_utterance(text, events);
+        if let Some(ref mut recorder) = self.recorder {
+            if let Err(e) = recorder.finish(text, self.endpointer.stats()) {
+                eprintln!("⚠️  保存录音失败: {:#}", e);
+            }
+        }
+        self.endpointer.reset();
+        self.recognizer.reset(stream);
     }
 }
@@ -0,0 +1,284 @@
+use crate::vad::VadStats;
+use crate::wav;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 语句开始前保留的音频（毫秒），补上 VAD 确认语音之前的开头
+const PRE_ROLL_MS: u64 = 500;
+
+/// 语句录制器
+///
+/// 缓存重采样后、AGC 之前的 16 kHz 音频，每个语句结束时写出一个 WAV 文件，
+/// 并附带同名的 JSON 元数据（识别文本、时间戳、设备名称和 VAD 统计）。
+/// 只在语句进行中缓存音频；语句之间只保留最近 500 ms 作为开头的预录，
+/// 长时间静音不会占用越来越多的内存。
+pub struct UtteranceRecorder {
+    dir: PathBuf,
+    device_name: String,
+    /// 来源标签（多路采集时区分说话方），同时用于文件名
+    label: Option<String>,
+    sample_rate: u32,
+    session: u64,
+    buffer: Vec<f32>,
+    pre_roll: VecDeque<f32>,
+    pre_roll_capacity: usize,
+    total_samples: u64,
+    utterance_start_sample: u64,
+    utterance_start_time: Option<SystemTime>,
+    next_index: u32,
+}
+
+#[derive(Serialize)]
+struct UtteranceMetadata<'a> {
+    index: u32,
+    text: &'a str,
+    device: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    sample_rate: u32,
+    /// 相对会话开始的偏移（秒），按样本数计算
+    start_seconds: f64,
+    end_seconds: f64,
+    /// Unix 时间戳（秒）
+    wall_clock_start: f64,
+    wall_clock_end: f64,
+    vad: VadStats,
+}
+
+impl UtteranceRecorder {
+    /// 创建录制器，目录不存在时自动创建
+    pub fn new(dir: &Path, device_name: &str, sample_rate: u32) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("无法创建录音目录: {}", dir.display()))?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            device_name: device_name.to_string(),
+            label: None,
+            sample_rate,
+            session: unix_seconds(SystemTime::now()) as u64,
+            buffer: Vec::new(),
+            pre_roll: VecDeque::new(),
+            pre_roll_capacity: (sample_rate as u64 * PRE_ROLL_MS / 1000) as usize,
+            total_samples: 0,
+            utterance_start_sample: 0,
+            utterance_start_time: None,
+            next_index: 1,
+        })
+    }
+
+    /// 更新设备名称（设备重连或切换后调用）
+    pub fn set_device_name(&mut self, name: &str) {
+        self.device_name = name.to_string();
+    }
+
+    /// 设置来源标签，文件名变为 `<会话>-<标签>-<序号>`
+    pub fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_string());
+    }
+
+    /// 追加音频；`in_utterance` 表示 VAD 已检测到当前语句的语音
+    ///
+    /// 语句开始前的音频只进入预录缓冲，语句开始时连同预录一起写入。
+    pub fn push(&mut self, samples: &[f32], in_utterance: bool) {
+        if self.utterance_start_time.is_none() {
+            if !in_utterance {
+                self.pre_roll.extend(samples);
+                let overflow = self.pre_roll.len().saturating_sub(self.pre_roll_capacity);
+                self.pre_roll.drain(..overflow);
+                self.total_samples += samples.len() as u64;
+                return;
+            }
+            self.utterance_start_time = Some(SystemTime::now());
+            self.utterance_start_sample = self.total_samples - self.pre_roll.len() as u64;
+            self.buffer.extend(self.pre_roll.drain(..));
+        }
+        self.buffer.extend_from_slice(samples);
+        self.total_samples += samples.len() as u64;
+    }
+
+    /// 结束当前语句并写出 WAV 和 JSON 文件
+    ///
+    /// 返回写出的 WAV 路径；没有缓存音频时返回 `None`。
+    pub fn finish(&mut self, text: &str, vad: VadStats) -> Result<Option<PathBuf>> {
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        let stem = match &self.label {
+            Some(label) => format!(
+                "{}-{}-{:04}",
+                self.session,
+                file_label(label),
+                self.next_index
+            ),
+            None => format!("{}-{:04}", self.session, self.next_index),
+        };
+        let wav_path = self.dir.join(format!("{}.wav", stem));
+        let json_path = self.dir.join(format!("{}.json", stem));
+
+        wav::write_wav(&wav_path, &self.buffer, self.sample_rate)?;
+
+        let rate = self.sample_rate as f64;
+        let start_time = self.utterance_start_time.unwrap_or_else(SystemTime::now);
+        let metadata = UtteranceMetadata {
+            index: self.next_index,
+            text,
+            device: &self.device_name,
+            source: self.label.as_deref(),
+            sample_rate: self.sample_rate,
+            start_seconds: self.utterance_start_sample as f64 / rate,
+            end_seconds: self.total_samples as f64 / rate,
+            wall_clock_start: unix_seconds(start_time),
+            wall_clock_end: unix_seconds(SystemTime::now()),
+            vad,
+        };
+        let json = serde_json::to_string_pretty(&metadata)?;
+        std::fs::write(&json_path, json)
+            .with_context(|| format!("无法写入元数据: {}", json_path.display()))?;
+
+        self.buffer.clear();
+        self.utterance_start_time = None;
+        self.next_index += 1;
+
+        Ok(Some(wav_path))
+    }
+}
+
+pub fn unix_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+/// 把来源标签转换为可以安全用于文件名的形式：
+/// 字母、数字、`-` 和 `_` 之外的字符（路径分隔符、`.`、空白等）替换为 `_`
+fn file_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_writes_wav_and_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "测试麦克风", 16000).unwrap();
+
+        recorder.push(&[0.1; 8000], true);
+        recorder.push(&[0.0; 8000], true);
+        assert!(!recorder.buffer.is_empty());
+
+        let wav_path = recorder
+            .finish("你好", VadStats::default())
+            .unwrap()
+            .unwrap();
+        assert!(wav_path.exists());
+        assert!(recorder.buffer.is_empty());
+
+        let json = std::fs::read_to_string(wav_path.with_extension("json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["text"], "你好");
+        assert_eq!(value["device"], "测试麦克风");
+        assert_eq!(value["start_seconds"], 0.0);
+        assert_eq!(value["end_seconds"], 1.0);
+    }
+
+    #[test]
+    fn test_recorder_offsets_accumulate() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "mic", 16000).unwrap();
+
+        recorder.push(&[0.0; 16000], true);
+        recorder.finish("一", VadStats::default()).unwrap();
+        recorder.push(&[0.0; 8000], true);
+        let second = recorder.finish("二", VadStats::default()).unwrap().unwrap();
+
+        let json = std::fs::read_to_string(second.with_extension("json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["index"], 2);
+        assert_eq!(value["start_seconds"], 1.0);
+        assert_eq!(value["end_seconds"], 1.5);
+    }
+
+    #[test]
+    fn test_recorder_finish_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "mic", 16000).unwrap();
+        assert!(recorder.finish("", VadStats::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_recorder_keeps_only_pre_roll_between_utterances() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "mic", 16000).unwrap();
+
+        // 一分钟静音只保留最近 500 ms
+        for _ in 0..600 {
+            recorder.push(&[0.0; 1600], false);
+        }
+        assert!(recorder.buffer.is_empty());
+        assert_eq!(recorder.pre_roll.len(), 8000);
+        assert!(recorder.finish("", VadStats::default()).unwrap().is_none());
+
+        recorder.push(&[0.1; 16000], true);
+        let path = recorder
+            .finish("你好", VadStats::default())
+            .unwrap()
+            .unwrap();
+        let (samples, _) = wav::read_wav(&path).unwrap();
+        assert_eq!(samples.len(), 24000);
+
+        let json = std::fs::read_to_string(path.with_extension("json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["start_seconds"], 59.5);
+        assert_eq!(value["end_seconds"], 61.0);
+    }
+
+    #[test]
+    fn test_recorder_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "mic", 16000).unwrap();
+        recorder.set_label("remote");
+        recorder.push(&[0.0; 1600], true);
+
+        let path = recorder.finish("好", VadStats::default()).unwrap().unwrap();
+        let stem = path.file_stem().unwrap().to_string_lossy().to_string();
+        assert!(stem.ends_with("-remote-0001"));
+
+        let json = std::fs::read_to_string(path.with_extension("json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["source"], "remote");
+    }
+
+    #[test]
+    fn test_recorder_label_is_sanitized() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut recorder = UtteranceRecorder::new(dir.path(), "mic", 16000).unwrap();
+        recorder.set_label("../远端 b");
+        recorder.push(&[0.0; 1600], true);
+
+        let path = recorder.finish("好", VadStats::default()).unwrap().unwrap();
+        assert_eq!(path.parent(), Some(dir.path()));
+        let stem = path.file_stem().unwrap().to_string_lossy().to_string();
+        assert!(stem.ends_with("-___远端_b-0001"));
+
+        // 元数据中保留原始标签
+        let json = std::fs::read_to_string(path.with_extension("json")).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["source"], "../远端 b");
+    }
+}
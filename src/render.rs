@@ -0,0 +1,184 @@
+use crate::audio::DeviceStatus;
+use crate::jsonl::{JsonlWriter, OutputFormat};
+use crate::segmenter::SourceEvent;
+use std::io::{IsTerminal, Stdout, Write};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// 无法获取终端宽度时使用的列数
+const DEFAULT_COLUMNS: usize = 80;
+/// 部分结果过长时，行首显示的省略号
+const ELLIPSIS: &str = "…";
+
+/// CLI 识别结果输出：按 `--format` 选择文本或 JSON Lines
+pub enum Output {
+    Text(LiveRenderer),
+    Jsonl(JsonlWriter<Stdout>),
+}
+
+impl Output {
+    /// `live` 为 `false` 时不原地刷新部分结果（见 [`LiveRenderer::new`]）
+    pub fn new(format: OutputFormat, live: bool) -> Self {
+        match format {
+            OutputFormat::Text => Self::Text(LiveRenderer::new(live)),
+            OutputFormat::Jsonl => Self::Jsonl(JsonlWriter::new(std::io::stdout())),
+        }
+    }
+
+    /// 输出一路来源产生的识别事件
+    pub fn events(&mut self, label: Option<&str>, events: &mut Vec<SourceEvent>) {
+        let prefix = label.map(|l| format!("[{}] ", l)).unwrap_or_default();
+        for event in events.drain(..) {
+            match self {
+                Self::Jsonl(writer) => writer.event(label, &event),
+                Self::Text(renderer) => match event {
+                    SourceEvent::Partial(text) => renderer.partial(&prefix, &text),
+                    SourceEvent::Final(text) => renderer.commit(&prefix, &text),
+                    SourceEvent::Endpoint(_) => {}
+                    SourceEvent::Error(message) => {
+                        renderer.clear();
+                        eprintln!("⚠️  {}{}", prefix, message);
+                    }
+                },
+            }
+        }
+    }
+
+    /// 输出设备状态变化；文本格式下返回 `false`，由调用方显示详细信息
+    pub fn device(&mut self, label: Option<&str>, status: &DeviceStatus) -> bool {
+        match self {
+            Self::Jsonl(writer) => {
+                writer.device(label, status);
+                true
+            }
+            Self::Text(renderer) => {
+                renderer.clear();
+                false
+            }
+        }
+    }
+
+    /// 清除文本格式下正在显示的部分结果
+    pub fn clear(&mut self) {
+        if let Self::Text(renderer) = self {
+            renderer.clear();
+        }
+    }
+}
+
+/// 文本格式的识别结果显示
+///
+/// 标准输出是终端时，部分结果在同一行上原地刷新，确认的文本另起一行；
+/// 重定向到文件或管道时只逐行输出确认的文本，不写入控制字符。
+pub struct LiveRenderer {
+    /// 是否原地刷新部分结果
+    live: bool,
+    /// 当前行上是否有尚未换行的部分结果
+    has_partial: bool,
+}
+
+impl LiveRenderer {
+    /// `live` 为 `false` 时即使输出到终端也只逐行输出确认的文本：
+    /// 调试输出（`--verbose`）会插进部分结果所在的行，多路来源会互相覆盖同一行
+    pub fn new(live: bool) -> Self {
+        Self {
+            live: live && std::io::stdout().is_terminal(),
+            has_partial: false,
+        }
+    }
+
+    /// 原地显示部分结果；文本为空时清除当前行
+    pub fn partial(&mut self, prefix: &str, text: &str) {
+        if !self.live {
+            return;
+        }
+        if text.is_empty() {
+            self.clear();
+            return;
+        }
+
+        // 最后一列留空，避免光标自动换行
+        let columns = terminal_columns().saturating_sub(1);
+        let line = fit_tail(&format!("{}{}", prefix, text), columns);
+        let mut stdout = std::io::stdout().lock();
+        let _ = write!(stdout, "\r\x1b[2K{}", line);
+        let _ = stdout.flush();
+        self.has_partial = true;
+    }
+
+    /// 输出确认的文本
+    pub fn commit(&mut self, prefix: &str, text: &str) {
+        self.clear();
+        println!("✅ {}{}", prefix, text);
+    }
+
+    /// 清除部分结果所在的行，之后可以正常输出其他信息
+    pub fn clear(&mut self) {
+        if !self.has_partial {
+            return;
+        }
+        let mut stdout = std::io::stdout().lock();
+        let _ = write!(stdout, "\r\x1b[2K");
+        let _ = stdout.flush();
+        self.has_partial = false;
+    }
+}
+
+/// 终端的列数
+fn terminal_columns() -> usize {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    if ok && size.ws_col > 0 {
+        return size.ws_col as usize;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_COLUMNS)
+}
+
+/// 按显示宽度截取文本末尾，使其不超过 `columns` 列
+///
+/// 中日韩文字占两列；超出时保留最新的内容，行首加省略号。
+fn fit_tail(text: &str, columns: usize) -> String {
+    if text.width() <= columns {
+        return text.to_string();
+    }
+
+    let budget = columns.saturating_sub(ELLIPSIS.width());
+    let mut width = 0;
+    let mut start = text.len();
+    for (idx, c) in text.char_indices().rev() {
+        width += c.width().unwrap_or(0);
+        if width > budget {
+            break;
+        }
+        start = idx;
+    }
+    format!("{}{}", ELLIPSIS, &text[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_tail_short_text_unchanged() {
+        assert_eq!(fit_tail("今天天气", 8), "今天天气");
+        assert_eq!(fit_tail("hello", 80), "hello");
+    }
+
+    #[test]
+    fn test_fit_tail_cjk_width() {
+        // 每个汉字占两列：9 列中省略号占一列，剩下 8 列放 4 个字
+        let fitted = fit_tail("我觉得今天天气不错", 9);
+        assert_eq!(fitted, "…天气不错");
+        assert!(fitted.width() <= 9);
+    }
+
+    #[test]
+    fn test_fit_tail_never_splits_wide_char() {
+        let fitted = fit_tail("ab今天", 4);
+        assert_eq!(fitted, "…天");
+        assert!(fitted.width() <= 4);
+    }
+}
@@ -1,13 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// 重采样算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResamplerKind {
+    /// 加窗 sinc 多相滤波，带抗混叠低通（默认）
+    #[default]
+    Sinc,
+    /// 线性插值，CPU 占用最低，但没有抗混叠滤波
+    Linear,
+}
+
+/// 根据配置选择的重采样器
+pub enum Resampler {
+    Linear(LinearResampler),
+    Sinc(SincResampler),
+}
+
+impl Resampler {
+    pub fn new(kind: ResamplerKind, input_rate: u32, output_rate: u32) -> Self {
+        match kind {
+            ResamplerKind::Linear => Self::Linear(LinearResampler::new(input_rate, output_rate)),
+            ResamplerKind::Sinc => Self::Sinc(SincResampler::new(input_rate, output_rate)),
+        }
+    }
+
+    /// 输入采样率与目标一致时不需要重采样，返回 `None`
+    pub fn new_if_needed(kind: ResamplerKind, input_rate: u32, output_rate: u32) -> Option<Self> {
+        (input_rate != output_rate).then(|| Self::new(kind, input_rate, output_rate))
+    }
+
+    pub fn resample(&mut self, input: &[f32]) -> Vec<f32> {
+        match self {
+            Self::Linear(r) => r.resample(input),
+            Self::Sinc(r) => r.resample(input),
+        }
+    }
+}
+
+/// 输入/输出采样率的最简整数比 `up / down`
+///
+/// 输出第 n 个样本对应输入位置 `n * down / up`，使用整数相位累加，
+/// 长时间运行也不会产生浮点位置漂移。
+#[derive(Debug, Clone, Copy)]
+struct Ratio {
+    up: u64,
+    down: u64,
+}
+
+impl Ratio {
+    fn new(input_rate: u32, output_rate: u32) -> Self {
+        let g = gcd(input_rate as u64, output_rate as u64).max(1);
+        Self {
+            up: output_rate as u64 / g,
+            down: input_rate as u64 / g,
+        }
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
 pub struct LinearResampler {
-    ratio: f32,
+    ratio: Ratio,
     buffer: Vec<f32>,
+    /// 下一个输出样本在 `buffer` 中的整数位置
+    pos: usize,
+    /// 下一个输出样本的小数位置，单位为 `1 / ratio.up`
+    phase: u64,
 }
 
 impl LinearResampler {
     pub fn new(input_rate: u32, output_rate: u32) -> Self {
         Self {
-            ratio: input_rate as f32 / output_rate as f32,
+            ratio: Ratio::new(input_rate, output_rate),
             buffer: Vec::new(),
+            pos: 0,
+            phase: 0,
         }
     }
 
@@ -17,31 +93,166 @@ impl LinearResampler {
         }
 
         self.buffer.extend_from_slice(input);
-        let output_len = (self.buffer.len() as f32 / self.ratio) as usize;
-        let mut output = Vec::with_capacity(output_len);
-
-        for i in 0..output_len {
-            let pos = i as f32 * self.ratio;
-            let idx = pos as usize;
-
-            if idx + 1 < self.buffer.len() {
-                let frac = pos - idx as f32;
-                let sample = self.buffer[idx] * (1.0 - frac) + self.buffer[idx + 1] * frac;
-                output.push(sample);
-            }
+        let up = self.ratio.up;
+        let mut output =
+            Vec::with_capacity((input.len() as u64 * up / self.ratio.down) as usize + 1);
+
+        while self.pos + 1 < self.buffer.len() {
+            let frac = self.phase as f32 / up as f32;
+            let sample = self.buffer[self.pos] * (1.0 - frac) + self.buffer[self.pos + 1] * frac;
+            output.push(sample);
+            self.advance();
         }
 
-        let consumed = (output_len as f32 * self.ratio) as usize;
-        self.buffer.drain(..consumed.min(self.buffer.len()));
+        let consumed = self.pos.min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.pos -= consumed;
 
         output
     }
+
+    fn advance(&mut self) {
+        self.phase += self.ratio.down;
+        self.pos += (self.phase / self.ratio.up) as usize;
+        self.phase %= self.ratio.up;
+    }
+}
+
+/// 每个方向上保留的 sinc 过零点数量，决定过渡带宽度
+const SINC_ZERO_CROSSINGS: usize = 16;
+/// 截止频率相对目标奈奎斯特频率的比例，留出过渡带
+const SINC_ROLLOFF: f64 = 0.92;
+/// Kaiser 窗参数，约 80 dB 阻带衰减
+const KAISER_BETA: f64 = 8.0;
+
+/// 加窗 sinc 多相重采样器
+///
+/// 对 `up / down` 的每个相位预先计算一组 FIR 系数（Kaiser 窗 sinc），
+/// 截止频率取输入、输出奈奎斯特频率中较低者，因此降采样时会先低通滤波，
+/// 避免 48 kHz 中的高频噪声混叠进 16 kHz 的语音频带。
+pub struct SincResampler {
+    ratio: Ratio,
+    /// 每个相位的滤波器系数，长度均为 `2 * half_len`
+    phases: Vec<Vec<f32>>,
+    half_len: usize,
+    buffer: Vec<f32>,
+    pos: usize,
+    phase: u64,
+}
+
+impl SincResampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let ratio = Ratio::new(input_rate, output_rate);
+        // 截止频率，单位为输入奈奎斯特频率
+        let cutoff = (ratio.up as f64 / ratio.down as f64).min(1.0) * SINC_ROLLOFF;
+        let half_len = (SINC_ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+
+        let phases = (0..ratio.up)
+            .map(|p| {
+                let frac = p as f64 / ratio.up as f64;
+                let mut taps: Vec<f64> = (0..2 * half_len)
+                    .map(|k| {
+                        // 第 k 个系数对应输入样本 pos - half_len + 1 + k
+                        let t = k as f64 - (half_len as f64 - 1.0) - frac;
+                        cutoff * sinc(cutoff * t) * kaiser(t / half_len as f64)
+                    })
+                    .collect();
+                // 每个相位单独归一化，保证直流增益为 1
+                let sum: f64 = taps.iter().sum();
+                if sum.abs() > f64::EPSILON {
+                    taps.iter_mut().for_each(|h| *h /= sum);
+                }
+                taps.into_iter().map(|h| h as f32).collect()
+            })
+            .collect();
+
+        Self {
+            ratio,
+            phases,
+            half_len,
+            // 预填充零样本，使第一个输出样本左侧也有完整的滤波器支撑
+            buffer: vec![0.0; half_len - 1],
+            pos: half_len - 1,
+            phase: 0,
+        }
+    }
+
+    pub fn resample(&mut self, input: &[f32]) -> Vec<f32> {
+        if input.is_empty() {
+            return Vec::new();
+        }
+
+        self.buffer.extend_from_slice(input);
+        let mut output =
+            Vec::with_capacity((input.len() as u64 * self.ratio.up / self.ratio.down) as usize + 1);
+
+        while self.pos + self.half_len < self.buffer.len() {
+            let start = self.pos + 1 - self.half_len;
+            let window = &self.buffer[start..start + 2 * self.half_len];
+            let taps = &self.phases[self.phase as usize];
+            let sample: f32 = window.iter().zip(taps).map(|(x, h)| x * h).sum();
+            output.push(sample);
+
+            self.phase += self.ratio.down;
+            self.pos += (self.phase / self.ratio.up) as usize;
+            self.phase %= self.ratio.up;
+        }
+
+        // 保留下一个输出样本所需的历史样本
+        let keep_from = (self.pos + 1).saturating_sub(self.half_len);
+        let consumed = keep_from.min(self.buffer.len());
+        self.buffer.drain(..consumed);
+        self.pos -= consumed;
+
+        output
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Kaiser 窗，`x` 取值范围 [-1, 1]
+fn kaiser(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / bessel_i0(KAISER_BETA)
+}
+
+/// 第一类零阶修正贝塞尔函数（级数展开）
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..50 {
+        term *= half / k as f64;
+        sum += term * term;
+        if term * term < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn tone(freq: f32, rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * std::f32::consts::PI * freq * i as f32 / rate as f32).sin())
+            .collect()
+    }
+
     #[test]
     fn test_resampler_basic() {
         let mut resampler = LinearResampler::new(48000, 16000);
@@ -65,4 +276,88 @@ mod tests {
         let output = resampler.resample(&input);
         assert_eq!(output.len(), 160);
     }
+
+    #[test]
+    fn test_linear_chunking_is_seamless() {
+        let input = tone(440.0, 44100, 44100);
+
+        let mut whole = LinearResampler::new(44100, 16000);
+        let expected = whole.resample(&input);
+
+        let mut chunked = LinearResampler::new(44100, 16000);
+        let mut actual = Vec::new();
+        for chunk in input.chunks(333) {
+            actual.extend(chunked.resample(chunk));
+        }
+
+        assert_eq!(actual.len(), expected.len());
+        for (a, b) in actual.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_sinc_output_count_has_no_drift() {
+        // 一分钟的 44.1 kHz 音频，按 441 样本分块
+        let mut resampler = SincResampler::new(44100, 16000);
+        let chunk = vec![0.0; 441];
+        let mut produced = 0usize;
+        for _ in 0..(60 * 100) {
+            produced += resampler.resample(&chunk).len();
+        }
+        let expected = 60 * 16000;
+        // 只差滤波器前瞻所需的最后几个样本
+        assert!(produced <= expected);
+        assert!(expected - produced <= resampler.half_len);
+    }
+
+    #[test]
+    fn test_sinc_preserves_dc() {
+        let mut resampler = SincResampler::new(48000, 16000);
+        let output = resampler.resample(&vec![0.5; 4800]);
+        let settled = &output[output.len() / 2..];
+        assert!(settled.iter().all(|s| (s - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_sinc_passes_speech_band() {
+        let mut resampler = SincResampler::new(48000, 16000);
+        let output = resampler.resample(&tone(1000.0, 48000, 48000));
+        let level = rms(&output[1000..]);
+        assert!((level - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_sinc_rejects_aliasing() {
+        // 20 kHz 的噪声在 16 kHz 采样下会混叠到 4 kHz
+        let input = tone(20000.0, 48000, 48000);
+
+        let mut linear = LinearResampler::new(48000, 16000);
+        let mut sinc = SincResampler::new(48000, 16000);
+        let linear_out = linear.resample(&input);
+        let sinc_out = sinc.resample(&input);
+
+        assert!(rms(&linear_out[1000..]) > 0.3);
+        assert!(rms(&sinc_out[1000..]) < 0.01);
+    }
+
+    #[test]
+    fn test_sinc_upsampling() {
+        let mut resampler = SincResampler::new(8000, 16000);
+        let output = resampler.resample(&tone(500.0, 8000, 8000));
+        assert!(output.len() > 15900 && output.len() <= 16000);
+        assert!((rms(&output[1000..]) - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_resampler_kind_dispatch() {
+        let mut linear = Resampler::new(ResamplerKind::Linear, 48000, 16000);
+        assert_eq!(linear.resample(&vec![1.0; 480]).len(), 160);
+
+        let mut sinc = Resampler::new(ResamplerKind::Sinc, 48000, 16000);
+        assert!(!sinc.resample(&vec![1.0; 4800]).is_empty());
+
+        assert!(Resampler::new_if_needed(ResamplerKind::Sinc, 16000, 16000).is_none());
+        assert!(Resampler::new_if_needed(ResamplerKind::Sinc, 44100, 16000).is_some());
+    }
 }
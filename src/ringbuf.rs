@@ -0,0 +1,231 @@
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// 单生产者单消费者无锁环形缓冲区
+///
+/// 缓冲区在创建时一次性分配，之后读写都不再分配内存，适合在音频回调中使用。
+/// 写入时空间不足的样本会被丢弃（不会覆盖尚未读取的数据），并计入溢出统计。
+///
+/// `head` 和 `tail` 是单调递增的计数器，取模后得到实际下标；
+/// 只有生产者写 `head`，只有消费者写 `tail`。
+struct Shared {
+    buffer: Box<[UnsafeCell<f32>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    overruns: AtomicU64,
+    dropped_samples: AtomicU64,
+}
+
+// 生产者只写 [head, tail + capacity) 内的空闲槽位，消费者只读 [tail, head)，
+// 两者通过 head/tail 的 Release/Acquire 同步，不会同时访问同一个槽位。
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+/// 创建容量为 `capacity` 个样本的环形缓冲区
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let buffer = (0..capacity.max(1))
+        .map(|_| UnsafeCell::new(0.0))
+        .collect::<Vec<_>>()
+        .into_boxed_slice();
+    let shared = Arc::new(Shared {
+        buffer,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        overruns: AtomicU64::new(0),
+        dropped_samples: AtomicU64::new(0),
+    });
+
+    (
+        Producer {
+            shared: Arc::clone(&shared),
+        },
+        Consumer { shared },
+    )
+}
+
+/// 写入端，由音频回调线程独占
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+impl Producer {
+    /// 写入样本，返回实际写入的数量；放不下的部分丢弃并记为一次溢出
+    pub fn push_slice(&mut self, samples: &[f32]) -> usize {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let free = shared.capacity() - head.wrapping_sub(tail);
+        let count = samples.len().min(free);
+
+        for (i, &sample) in samples[..count].iter().enumerate() {
+            let slot = &shared.buffer[head.wrapping_add(i) % shared.capacity()];
+            // SAFETY: 该槽位不在 [tail, head) 内，消费者不会读取
+            unsafe { *slot.get() = sample };
+        }
+        shared
+            .head
+            .store(head.wrapping_add(count), Ordering::Release);
+
+        if count < samples.len() {
+            shared.overruns.fetch_add(1, Ordering::Relaxed);
+            shared
+                .dropped_samples
+                .fetch_add((samples.len() - count) as u64, Ordering::Relaxed);
+        }
+        count
+    }
+}
+
+/// 读取端，由处理线程独占
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+impl Consumer {
+    /// 可读取的样本数
+    pub fn len(&self) -> usize {
+        let head = self.shared.head.load(Ordering::Acquire);
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        head.wrapping_sub(tail)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 读出全部可用样本，追加到 `out`，返回读取的数量
+    pub fn pop_into(&mut self, out: &mut Vec<f32>) -> usize {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let count = head.wrapping_sub(tail);
+
+        out.reserve(count);
+        for i in 0..count {
+            let slot = &shared.buffer[tail.wrapping_add(i) % shared.capacity()];
+            // SAFETY: 该槽位在 [tail, head) 内，生产者不会写入
+            out.push(unsafe { *slot.get() });
+        }
+        shared
+            .tail
+            .store(tail.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// 丢弃全部可用样本，返回丢弃的数量
+    pub fn clear(&mut self) -> usize {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        shared.tail.store(head, Ordering::Release);
+        head.wrapping_sub(tail)
+    }
+
+    /// 发生溢出（写入时空间不足）的次数
+    pub fn overruns(&self) -> u64 {
+        self.shared.overruns.load(Ordering::Relaxed)
+    }
+
+    /// 因溢出丢弃的样本数
+    pub fn dropped_samples(&self) -> u64 {
+        self.shared.dropped_samples.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let (mut producer, mut consumer) = ring_buffer(8);
+        assert!(consumer.is_empty());
+        assert_eq!(producer.push_slice(&[1.0, 2.0, 3.0]), 3);
+        assert_eq!(consumer.len(), 3);
+
+        let mut out = Vec::new();
+        assert_eq!(consumer.pop_into(&mut out), 3);
+        assert_eq!(out, vec![1.0, 2.0, 3.0]);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_wraps_around() {
+        let (mut producer, mut consumer) = ring_buffer(4);
+        let mut out = Vec::new();
+        for round in 0..10 {
+            let base = round as f32 * 3.0;
+            producer.push_slice(&[base, base + 1.0, base + 2.0]);
+            out.clear();
+            consumer.pop_into(&mut out);
+            assert_eq!(out, vec![base, base + 1.0, base + 2.0]);
+        }
+        assert_eq!(consumer.overruns(), 0);
+    }
+
+    #[test]
+    fn test_overrun_drops_newest_and_counts() {
+        let (mut producer, mut consumer) = ring_buffer(4);
+        assert_eq!(producer.push_slice(&[1.0, 2.0, 3.0]), 3);
+        assert_eq!(producer.push_slice(&[4.0, 5.0, 6.0]), 1);
+        assert_eq!(consumer.overruns(), 1);
+        assert_eq!(consumer.dropped_samples(), 2);
+
+        let mut out = Vec::new();
+        consumer.pop_into(&mut out);
+        assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_clear_while_idle_prevents_overruns() {
+        let (mut producer, mut consumer) = ring_buffer(8);
+        // 待机期间每轮都丢弃已采集的数据，写入总量远超容量也不会溢出
+        for _ in 0..10 {
+            assert_eq!(producer.push_slice(&[1.0; 6]), 6);
+            assert_eq!(consumer.clear(), 6);
+        }
+        assert_eq!(consumer.overruns(), 0);
+        assert!(consumer.is_empty());
+
+        // 不读取时很快就会溢出
+        producer.push_slice(&[1.0; 6]);
+        producer.push_slice(&[1.0; 6]);
+        assert_eq!(consumer.overruns(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_transfer() {
+        let (mut producer, mut consumer) = ring_buffer(64);
+        let total = 100_000;
+
+        let writer = std::thread::spawn(move || {
+            let mut next = 0;
+            while next < total {
+                let end = (next + 17).min(total);
+                let chunk: Vec<f32> = (next..end).map(|i| i as f32).collect();
+                let written = producer.push_slice(&chunk);
+                next += written;
+                // 写满时等待消费者读取，这里只检查数据顺序和完整性
+                if written < chunk.len() {
+                    std::thread::yield_now();
+                }
+            }
+        });
+
+        let mut received = Vec::with_capacity(total);
+        while received.len() < total {
+            if consumer.pop_into(&mut received) == 0 {
+                std::thread::yield_now();
+            }
+        }
+        writer.join().unwrap();
+
+        assert!(received.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+}
@@ -0,0 +1,268 @@
+/// 识别事件
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceEvent {
+    /// 当前语句尚未确认的部分（说话过程中随识别假设变化，可能为空）
+    Partial(String),
+    /// 新确认的文本；已确认的内容不会再次输出
+    Final(String),
+    /// 语句结束，附带整句的识别结果；在该句所有 `Final` 之后产生
+    Endpoint(Utterance),
+    /// 处理过程中的非致命错误（如保存录音失败）
+    Error(String),
+}
+
+/// 一个完整语句的识别结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Utterance {
+    pub text: String,
+    pub tokens: Vec<String>,
+    /// 每个 token 在输入音频中的开始时间（秒）；模型不提供时为空
+    pub timestamps: Vec<f32>,
+    /// 每个 token 的置信度（0–1）；模型不提供时为空
+    pub confidences: Vec<f32>,
+    /// 语句中语音在输入音频中的起止时间（秒），按 VAD 去掉了首尾的静音
+    pub start: f64,
+    pub end: f64,
+    /// 断句原因；输入结束时为 `None`
+    pub reason: Option<String>,
+}
+
+/// 把识别器不断修正的假设切分为已确认文本和未确认的部分结果
+///
+/// 流式识别器每次解码都会给出整句的新假设。分段器记录当前语句中已经
+/// 确认（打印或注入）的前缀，只输出此后新确认的文本，部分结果也只包含
+/// 尚未确认的部分，CLI、GUI 等各种输出共用，避免同一句话重复出现。
+///
+/// 语句中出现句末标点时，标点之前的内容立即确认，不必等到断句；
+/// 断句时剩余部分全部确认。
+#[derive(Debug, Default)]
+pub struct Segmenter {
+    /// 当前语句已确认的前缀（识别器假设中的原文）
+    committed: String,
+    /// 上次输出的部分结果
+    partial: String,
+}
+
+impl Segmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 识别器给出新假设：确认完整的句子，部分结果变化时输出
+    pub fn update(&mut self, hypothesis: &str, events: &mut Vec<SourceEvent>) {
+        let hypothesis = hypothesis.trim();
+        let pending = self.pending(hypothesis);
+        if let Some(end) = sentence_end(pending) {
+            let sentence = pending[..end].trim().to_string();
+            self.committed = hypothesis[..hypothesis.len() - pending.len() + end].to_string();
+            events.push(SourceEvent::Final(sentence));
+        }
+
+        let pending = self.pending(hypothesis).trim().to_string();
+        if pending != self.partial {
+            self.partial = pending.clone();
+            events.push(SourceEvent::Partial(pending));
+        }
+    }
+
+    /// 语句结束：确认剩余的文本，为下一句重置状态
+    pub fn end_utterance(&mut self, final_result: &str, events: &mut Vec<SourceEvent>) {
+        let remaining = self.pending(final_result.trim()).trim().to_string();
+        if !remaining.is_empty() {
+            events.push(SourceEvent::Final(remaining));
+        }
+        if !self.partial.is_empty() {
+            events.push(SourceEvent::Partial(String::new()));
+        }
+        self.committed.clear();
+        self.partial.clear();
+    }
+
+    /// 假设中尚未确认的部分
+    ///
+    /// 识别器偶尔会修改已确认的前缀，此时已经输出的内容无法撤回：
+    /// 把已确认文本与假设的各个前缀对齐，跳过编辑距离最小的那个前缀，
+    /// 修改前后长度不同时也不会丢字或重复。
+    fn pending<'a>(&self, hypothesis: &'a str) -> &'a str {
+        if let Some(rest) = hypothesis.strip_prefix(self.committed.as_str()) {
+            return rest;
+        }
+        &hypothesis[aligned_prefix_len(&self.committed, hypothesis)..]
+    }
+}
+
+/// 与 `committed` 编辑距离最小的假设前缀的字节长度；距离相同时取较长的前缀
+fn aligned_prefix_len(committed: &str, hypothesis: &str) -> usize {
+    let hypothesis_chars: Vec<(usize, char)> = hypothesis.char_indices().collect();
+    // row[j]：已处理的已确认字符与假设前 j 个字符之间的编辑距离
+    let mut row: Vec<usize> = (0..=hypothesis_chars.len()).collect();
+    for (i, c) in committed.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &(_, h)) in hypothesis_chars.iter().enumerate() {
+            let value = (diagonal + usize::from(c != h))
+                .min(row[j + 1] + 1)
+                .min(row[j] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = value;
+        }
+    }
+
+    let best = (0..row.len())
+        .min_by_key(|&j| (row[j], std::cmp::Reverse(j)))
+        .unwrap_or(0);
+    hypothesis_chars
+        .get(best)
+        .map_or(hypothesis.len(), |&(idx, _)| idx)
+}
+
+/// 不作为句末的缩写（不区分大小写，不含最后的句点）
+const ABBREVIATIONS: [&str; 8] = ["mr", "mrs", "ms", "dr", "prof", "st", "e.g", "i.e"];
+
+/// 文本中最后一个句末标点之后的字节位置
+fn sentence_end(text: &str) -> Option<usize> {
+    text.char_indices()
+        .rev()
+        .find(|&(idx, c)| match c {
+            '。' | '？' | '！' | '?' | '!' => true,
+            '.' => is_full_stop(text, idx),
+            _ => false,
+        })
+        .map(|(idx, c)| idx + c.len_utf8())
+}
+
+/// `text[idx]` 处的 ASCII 句点是否为句末
+///
+/// 句点后必须是空白或文本结尾，排除 `3.14` 这样的小数（包括尚未识别出
+/// 小数部分的 `3.`）、`Mr.` / `e.g.` 等缩写和姓名首字母。
+fn is_full_stop(text: &str, idx: usize) -> bool {
+    let before = &text[..idx];
+    match text[idx + 1..].chars().next() {
+        Some(next) if !next.is_whitespace() => return false,
+        None if before.ends_with(|c: char| c.is_ascii_digit()) => return false,
+        _ => {}
+    }
+
+    let word = before.rsplit(char::is_whitespace).next().unwrap_or("");
+    let initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+    !initial && !ABBREVIATIONS.iter().any(|a| word.eq_ignore_ascii_case(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(segmenter: &mut Segmenter, hypothesis: &str) -> Vec<SourceEvent> {
+        let mut events = Vec::new();
+        segmenter.update(hypothesis, &mut events);
+        events
+    }
+
+    fn end(segmenter: &mut Segmenter, final_result: &str) -> Vec<SourceEvent> {
+        let mut events = Vec::new();
+        segmenter.end_utterance(final_result, &mut events);
+        events
+    }
+
+    fn partial(text: &str) -> SourceEvent {
+        SourceEvent::Partial(text.to_string())
+    }
+
+    fn committed(text: &str) -> SourceEvent {
+        SourceEvent::Final(text.to_string())
+    }
+
+    #[test]
+    fn test_partial_only_on_change() {
+        let mut segmenter = Segmenter::new();
+        assert_eq!(update(&mut segmenter, "今天"), vec![partial("今天")]);
+        assert!(update(&mut segmenter, "今天 ").is_empty());
+        assert_eq!(
+            update(&mut segmenter, "今天天气"),
+            vec![partial("今天天气")]
+        );
+    }
+
+    #[test]
+    fn test_endpoint_commits_once() {
+        let mut segmenter = Segmenter::new();
+        update(&mut segmenter, "今天天气");
+        assert_eq!(
+            end(&mut segmenter, "今天天气不错"),
+            vec![committed("今天天气不错"), partial("")]
+        );
+        // 下一句从头开始
+        assert_eq!(update(&mut segmenter, "明天"), vec![partial("明天")]);
+    }
+
+    #[test]
+    fn test_sentence_punctuation_commits_early() {
+        let mut segmenter = Segmenter::new();
+        assert_eq!(
+            update(&mut segmenter, "你好。我是"),
+            vec![committed("你好。"), partial("我是")]
+        );
+        assert_eq!(
+            update(&mut segmenter, "你好。我是小明"),
+            vec![partial("我是小明")]
+        );
+        assert_eq!(
+            end(&mut segmenter, "你好。我是小明"),
+            vec![committed("我是小明"), partial("")]
+        );
+    }
+
+    #[test]
+    fn test_revised_prefix_not_repeated() {
+        let mut segmenter = Segmenter::new();
+        update(&mut segmenter, "Hello world. How");
+        // 识别器修改了已确认的部分，只输出新增内容
+        assert_eq!(
+            end(&mut segmenter, "Hello word. How are you"),
+            vec![committed("How are you"), partial("")]
+        );
+    }
+
+    #[test]
+    fn test_revision_changing_length() {
+        let mut segmenter = Segmenter::new();
+        update(&mut segmenter, "你们好。我");
+        // 已确认部分变短：不丢掉“我”
+        assert_eq!(update(&mut segmenter, "你好。我是"), vec![partial("我是")]);
+
+        let mut segmenter = Segmenter::new();
+        update(&mut segmenter, "今天天气。明");
+        // 已确认部分变长：不重复句号
+        assert_eq!(
+            update(&mut segmenter, "今天的天气。明天好"),
+            vec![partial("明天好")]
+        );
+    }
+
+    #[test]
+    fn test_period_inside_numbers_and_abbreviations() {
+        let mut segmenter = Segmenter::new();
+        assert_eq!(
+            update(&mut segmenter, "pi is 3."),
+            vec![partial("pi is 3.")]
+        );
+        assert_eq!(
+            update(&mut segmenter, "pi is 3.14 and"),
+            vec![partial("pi is 3.14 and")]
+        );
+        assert_eq!(
+            update(&mut segmenter, "Ask Mr. Smith, e.g. today. Then"),
+            vec![committed("Ask Mr. Smith, e.g. today."), partial("Then")]
+        );
+        assert_eq!(sentence_end("J. R. R. Tolkien"), None);
+        assert_eq!(sentence_end("done."), Some(5));
+    }
+
+    #[test]
+    fn test_empty_utterance_emits_nothing() {
+        let mut segmenter = Segmenter::new();
+        assert!(end(&mut segmenter, "").is_empty());
+        update(&mut segmenter, "好。");
+        assert!(end(&mut segmenter, "好。").is_empty());
+    }
+}
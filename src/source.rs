@@ -0,0 +1,357 @@
+use crate::audio::{AudioCapture, CaptureOptions, CaptureStats, DeviceStatus, TARGET_SAMPLE_RATE};
+use crate::config::Config;
+use crate::ffi::OnlineRecognizer;
+use crate::monitor;
+use crate::preprocess::Preprocessor;
+use crate::recorder::UtteranceRecorder;
+use crate::segmenter::SourceEvent;
+use crate::transcriber::Transcriber;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 采集来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureSource {
+    /// 麦克风（默认）
+    #[default]
+    Mic,
+    /// 系统音频（PulseAudio / PipeWire 监视源）
+    Loopback,
+    /// 麦克风和系统音频分别识别，结果带来源标签
+    Both,
+}
+
+impl std::str::FromStr for CaptureSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "mic" => Ok(Self::Mic),
+            "loopback" => Ok(Self::Loopback),
+            "both" => Ok(Self::Both),
+            _ => anyhow::bail!("无效的采集来源: {}（可选 mic、loopback、both）", s),
+        }
+    }
+}
+
+/// 配置文件中 `[[sources]]` 定义的一路输入
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceConfig {
+    /// 来源标签，显示在识别结果前并用于录音文件名
+    pub label: String,
+    /// 设备名称（与 `--list-devices` 显示的一致）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// 设备索引；同时设置名称时以名称为准
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_index: Option<usize>,
+    /// 系统音频监视源，设置后忽略设备名称和索引
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loopback: Option<String>,
+    /// 该设备使用的输入声道，未设置时沿用全局 `input_channels`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_channels: Option<Vec<u16>>,
+}
+
+impl std::str::FromStr for SourceConfig {
+    type Err = anyhow::Error;
+
+    /// 解析命令行中的 `标签=设备名称`
+    fn from_str(s: &str) -> Result<Self> {
+        let (label, device) = s
+            .split_once('=')
+            .filter(|(label, device)| !label.is_empty() && !device.is_empty())
+            .with_context(|| format!("无效的输入来源: {}（格式为 标签=设备名称）", s))?;
+        Ok(Self {
+            label: label.to_string(),
+            device: Some(device.to_string()),
+            ..Self::default()
+        })
+    }
+}
+
+/// 一路音频来源
+#[derive(Debug, Clone)]
+pub struct SourceSpec {
+    /// 输出和录音中使用的标签；只有一路来源时为 `None`
+    pub label: Option<String>,
+    pub options: CaptureOptions,
+}
+
+impl SourceSpec {
+    /// 按配置生成要打开的来源列表
+    ///
+    /// 配置了 `[[sources]]` 时按其逐一打开设备，忽略 `capture_source`
+    /// 和命令行的 `--device` / `--device-name`。
+    pub fn from_config(
+        config: &Config,
+        device_index: Option<usize>,
+        device_name: Option<String>,
+    ) -> Result<Vec<SourceSpec>> {
+        if !config.sources.is_empty() {
+            return Self::from_sources(config);
+        }
+
+        let mic = CaptureOptions::from_config(config, device_index, device_name);
+        let loopback = CaptureOptions {
+            monitor: Some(monitor::resolve_source(&config.loopback_source).to_string()),
+            device_index: None,
+            device_name: None,
+            // 系统音频的声道布局与麦克风无关，始终使用全部声道
+            input_channels: Vec::new(),
+            ..mic.clone()
+        };
+
+        Ok(match config.capture_source {
+            CaptureSource::Mic => vec![SourceSpec {
+                label: None,
+                options: mic,
+            }],
+            CaptureSource::Loopback => vec![SourceSpec {
+                label: None,
+                options: loopback,
+            }],
+            CaptureSource::Both => vec![
+                SourceSpec {
+                    label: Some(config.mic_label.clone()),
+                    options: mic,
+                },
+                SourceSpec {
+                    label: Some(config.loopback_label.clone()),
+                    options: loopback,
+                },
+            ],
+        })
+    }
+
+    fn from_sources(config: &Config) -> Result<Vec<SourceSpec>> {
+        let mut specs: Vec<SourceSpec> = Vec::with_capacity(config.sources.len());
+        for source in &config.sources {
+            if source.label.is_empty() {
+                anyhow::bail!("输入来源缺少 label");
+            }
+            if specs
+                .iter()
+                .any(|s| s.label.as_deref() == Some(source.label.as_str()))
+            {
+                anyhow::bail!("输入来源标签重复: {}", source.label);
+            }
+
+            let mut options = CaptureOptions::from_config(
+                config,
+                source.device_index.filter(|_| source.device.is_none()),
+                source.device.clone(),
+            );
+            options.no_fallback = true;
+            if let Some(channels) = &source.input_channels {
+                options.input_channels = channels.clone();
+            }
+            if let Some(loopback) = &source.loopback {
+                options.monitor = Some(monitor::resolve_source(loopback).to_string());
+                options.device_index = None;
+                options.device_name = None;
+            }
+
+            specs.push(SourceSpec {
+                label: Some(source.label.clone()),
+                options,
+            });
+        }
+        Ok(specs)
+    }
+}
+
+/// 一路来源：采集设备加独立的识别处理链
+pub struct SourcePipeline {
+    label: Option<String>,
+    capture: AudioCapture,
+    transcriber: Transcriber,
+    last_stats: CaptureStats,
+    verbose: bool,
+}
+
+impl SourcePipeline {
+    pub fn open(
+        spec: SourceSpec,
+        config: &Config,
+        recognizer: &OnlineRecognizer,
+        verbose: bool,
+    ) -> Result<Self> {
+        let capture = AudioCapture::open(spec.options).with_context(|| match &spec.label {
+            Some(label) => format!("无法打开来源 {}", label),
+            None => "无法打开音频设备".to_string(),
+        })?;
+        let name = spec.label.as_deref().unwrap_or("主循环");
+        let transcriber = Transcriber::new(
+            config,
+            recognizer,
+            capture.config().sample_rate(),
+            name,
+            verbose,
+        )?;
+
+        Ok(Self {
+            label: spec.label,
+            capture,
+            transcriber,
+            last_stats: CaptureStats::default(),
+            verbose,
+        })
+    }
+
+    /// 启用语句录音；多路来源时文件名带来源标签
+    pub fn enable_recording(&mut self, dir: &Path) -> Result<()> {
+        let mut recorder =
+            UtteranceRecorder::new(dir, self.capture.device_name(), TARGET_SAMPLE_RATE)?;
+        if let Some(label) = &self.label {
+            recorder.set_label(label);
+        }
+        self.transcriber.set_recorder(recorder);
+        Ok(())
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn capture(&self) -> &AudioCapture {
+        &self.capture
+    }
+
+    pub fn preprocessor(&self) -> &Preprocessor {
+        self.transcriber.preprocessor()
+    }
+
+    /// 检查设备状态；切换到新的流后按新采样率重建预处理链和重采样器
+    pub fn poll_device(&mut self) -> Option<DeviceStatus> {
+        let status = self.capture.poll();
+        if self.capture.take_reopened() {
+            self.transcriber
+                .set_sample_rate(self.capture.config().sample_rate());
+            if let Some(recorder) = self.transcriber.recorder_mut() {
+                recorder.set_device_name(self.capture.device_name());
+            }
+        }
+        status
+    }
+
+    /// 处理已采集的音频，产生的识别事件追加到 `events`
+    ///
+    /// 返回是否读取到了音频，调用方据此决定是否需要等待。
+    pub fn step(&mut self, recognizer: &OnlineRecognizer, events: &mut Vec<SourceEvent>) -> bool {
+        let Some(samples) = self.capture.try_recv() else {
+            return false;
+        };
+        if self.verbose {
+            eprintln!(
+                "[DEBUG] {}: 接收到 {} 个样本",
+                self.debug_name(),
+                samples.len()
+            );
+            let stats = self.capture.stats();
+            if stats != self.last_stats {
+                eprintln!("[DEBUG] {}: 采集 {}", self.debug_name(), stats);
+                self.last_stats = stats;
+            }
+        }
+
+        self.transcriber.accept(recognizer, samples, events);
+        true
+    }
+
+    /// 退出前冲刷模型中剩余的音频，输出并保存最后一句
+    pub fn finish(&mut self, recognizer: &OnlineRecognizer, events: &mut Vec<SourceEvent>) {
+        self.transcriber.finish(recognizer, events);
+    }
+
+    fn debug_name(&self) -> &str {
+        self.label.as_deref().unwrap_or("主循环")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_source_from_str() {
+        assert_eq!("mic".parse::<CaptureSource>().unwrap(), CaptureSource::Mic);
+        assert_eq!(
+            "Loopback".parse::<CaptureSource>().unwrap(),
+            CaptureSource::Loopback
+        );
+        assert!("speaker".parse::<CaptureSource>().is_err());
+    }
+
+    #[test]
+    fn test_specs_single_source_unlabelled() {
+        let specs = SourceSpec::from_config(&Config::default(), Some(1), None).unwrap();
+        assert_eq!(specs.len(), 1);
+        assert!(specs[0].label.is_none());
+        assert_eq!(specs[0].options.device_index, Some(1));
+        assert!(specs[0].options.monitor.is_none());
+    }
+
+    #[test]
+    fn test_specs_both_sources_labelled() {
+        let config = Config {
+            capture_source: CaptureSource::Both,
+            input_channels: vec![2],
+            ..Config::default()
+        };
+        let specs = SourceSpec::from_config(&config, None, None).unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].label.as_deref(), Some("me"));
+        assert_eq!(specs[1].label.as_deref(), Some("remote"));
+        assert_eq!(
+            specs[1].options.monitor.as_deref(),
+            Some(monitor::DEFAULT_MONITOR)
+        );
+        assert!(specs[1].options.input_channels.is_empty());
+    }
+
+    #[test]
+    fn test_specs_from_sources() {
+        let config = Config {
+            capture_source: CaptureSource::Both,
+            input_channels: vec![1],
+            sources: vec![
+                "alice=USB Audio A".parse().unwrap(),
+                SourceConfig {
+                    label: "bob".to_string(),
+                    device_index: Some(3),
+                    input_channels: Some(vec![2]),
+                    ..SourceConfig::default()
+                },
+            ],
+            ..Config::default()
+        };
+        let specs = SourceSpec::from_config(&config, Some(0), None).unwrap();
+        assert_eq!(specs.len(), 2);
+        assert_eq!(specs[0].label.as_deref(), Some("alice"));
+        assert_eq!(specs[0].options.device_name.as_deref(), Some("USB Audio A"));
+        assert_eq!(specs[0].options.device_index, None);
+        assert_eq!(specs[0].options.input_channels, vec![1]);
+        assert_eq!(specs[1].options.device_index, Some(3));
+        assert_eq!(specs[1].options.input_channels, vec![2]);
+    }
+
+    #[test]
+    fn test_sources_require_unique_labels() {
+        let config = Config {
+            sources: vec!["a=x".parse().unwrap(), "a=y".parse().unwrap()],
+            ..Config::default()
+        };
+        assert!(SourceSpec::from_config(&config, None, None).is_err());
+    }
+
+    #[test]
+    fn test_source_config_from_str() {
+        assert!("alice".parse::<SourceConfig>().is_err());
+        assert!("=device".parse::<SourceConfig>().is_err());
+        let source: SourceConfig = "a=USB=2".parse().unwrap();
+        assert_eq!(source.device.as_deref(), Some("USB=2"));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::config::Config;
+use crate::export::{self, TimedWord};
+use crate::segmenter::Utterance;
+use unicode_width::UnicodeWidthStr;
+
+/// 显示时间短于该值（秒）的字幕适当延长，但不与下一条重叠
+const MIN_CUE_SECONDS: f64 = 0.7;
+
+/// 一条字幕
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub lines: Vec<String>,
+}
+
+/// 字幕排版规则
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubtitleParams {
+    /// 每行最大宽度（列），中日韩文字按两列计
+    pub max_line_width: usize,
+    pub max_lines: usize,
+    /// 每条字幕最长显示时长（秒）
+    pub max_cue_seconds: f64,
+}
+
+impl SubtitleParams {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_line_width: config.subtitle_max_line_width.max(1),
+            max_lines: config.subtitle_max_lines.max(1),
+            max_cue_seconds: config.subtitle_max_cue_ms as f64 / 1000.0,
+        }
+    }
+}
+
+/// 把转写结果排成字幕
+///
+/// 每个语句（VAD 断句的结果）单独分条，字幕不跨越语句间的停顿。语句内
+/// 按以下规则切分：句末标点处结束一条；超出行宽 × 行数或最长时长时切分，
+/// 优先在后半部分的逗号等句中标点处切开。
+pub fn cues(utterances: &[Utterance], config: &Config) -> Vec<Cue> {
+    let params = SubtitleParams::from_config(config);
+    let mut cues: Vec<Cue> = utterances
+        .iter()
+        .flat_map(|utterance| {
+            let words = export::timed_words(utterance);
+            split_cues(&words, &params)
+                .into_iter()
+                .map(|group| Cue {
+                    start: group[0].start,
+                    end: group[group.len() - 1].end,
+                    lines: wrap_lines(group, &params),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // 过短的字幕延长显示，并保证前后两条不重叠
+    for i in 0..cues.len() {
+        let next_start = cues.get(i + 1).map_or(f64::INFINITY, |next| next.start);
+        let cue = &mut cues[i];
+        cue.end = cue.end.max(cue.start + MIN_CUE_SECONDS).min(next_start);
+    }
+    cues
+}
+
+/// 按标点、宽度和时长把一个语句的词分成若干条字幕
+fn split_cues<'a>(words: &'a [TimedWord], params: &SubtitleParams) -> Vec<&'a [TimedWord]> {
+    let max_width = params.max_line_width * params.max_lines;
+    let mut groups = Vec::new();
+    let mut begin = 0;
+    while begin < words.len() {
+        let mut end = begin;
+        let mut width = 0;
+        let mut clause_end = None;
+        let mut sentence_ended = false;
+        while end < words.len() {
+            let word = &words[end];
+            let add = word_width(word, end > begin);
+            let overflow =
+                width + add > max_width || word.end - words[begin].start > params.max_cue_seconds;
+            if end > begin && overflow {
+                break;
+            }
+            width += add;
+            end += 1;
+            if ends_with(word, &['。', '？', '！', '.', '?', '!']) {
+                sentence_ended = true;
+                break;
+            }
+            if ends_with(word, &['，', '、', '；', '：', ',', ';', ':']) {
+                clause_end = Some(end);
+            }
+        }
+
+        // 因超长切分时，尽量退回到后半部分的句中标点处
+        if !sentence_ended && end < words.len() {
+            if let Some(clause) = clause_end.filter(|&c| (c - begin) * 2 >= end - begin) {
+                end = clause;
+            }
+        }
+        groups.push(&words[begin..end]);
+        begin = end;
+    }
+    groups
+}
+
+/// 把一条字幕的词折成行，各行宽度尽量均衡
+fn wrap_lines(words: &[TimedWord], params: &SubtitleParams) -> Vec<String> {
+    let total: usize = words
+        .iter()
+        .enumerate()
+        .map(|(i, w)| word_width(w, i > 0))
+        .sum();
+    let line_count = total
+        .div_ceil(params.max_line_width)
+        .clamp(1, params.max_lines);
+    let target = total.div_ceil(line_count);
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in words {
+        let add = word_width(word, !line.is_empty());
+        let width = line.width();
+        let full = width + add > params.max_line_width
+            || (width + add > target && lines.len() + 1 < line_count);
+        if !line.is_empty() && full {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() && word.space_before {
+            line.push(' ');
+        }
+        line.push_str(&word.text);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// 词的显示宽度，`spaced` 时计入前面的空格
+fn word_width(word: &TimedWord, spaced: bool) -> usize {
+    word.text.width() + usize::from(spaced && word.space_before)
+}
+
+fn ends_with(word: &TimedWord, punctuation: &[char]) -> bool {
+    word.text.ends_with(punctuation)
+}
+
+/// SubRip（.srt）格式
+pub fn to_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            timestamp(cue.start, ','),
+            timestamp(cue.end, ','),
+            cue.lines.join("\n")
+        ));
+    }
+    out
+}
+
+/// WebVTT（.vtt）格式
+pub fn to_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            cue.lines.join("\n")
+        ));
+    }
+    out
+}
+
+/// `HH:MM:SS,mmm`（SRT）或 `HH:MM:SS.mmm`（WebVTT）
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(max_line_width: usize, max_lines: usize, max_cue_seconds: f64) -> SubtitleParams {
+        SubtitleParams {
+            max_line_width,
+            max_lines,
+            max_cue_seconds,
+        }
+    }
+
+    /// 每个字符一个词，间隔 0.2 秒
+    fn cjk_words(text: &str) -> Vec<TimedWord> {
+        let mut words: Vec<TimedWord> = Vec::new();
+        for c in text.chars() {
+            if export::is_punctuation(c) {
+                words.last_mut().unwrap().text.push(c);
+                continue;
+            }
+            let start = words.len() as f64 * 0.2;
+            words.push(TimedWord {
+                text: c.to_string(),
+                start,
+                end: start + 0.2,
+                space_before: false,
+                confidence: None,
+            });
+        }
+        words
+    }
+
+    fn joined(groups: &[&[TimedWord]]) -> Vec<String> {
+        groups
+            .iter()
+            .map(|g| g.iter().map(|w| w.text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_at_sentence_end() {
+        let words = cjk_words("你好。今天天气不错。");
+        let groups = split_cues(&words, &params(42, 2, 6.0));
+        assert_eq!(joined(&groups), ["你好。", "今天天气不错。"]);
+    }
+
+    #[test]
+    fn test_split_prefers_clause_punctuation() {
+        // 每条最多 2 × 9 = 18 列（9 个汉字）
+        let words = cjk_words("我们今天开会，讨论下周的发布计划");
+        let groups = split_cues(&words, &params(9, 2, 60.0));
+        assert_eq!(joined(&groups), ["我们今天开会，", "讨论下周的发布计划"]);
+    }
+
+    #[test]
+    fn test_split_by_max_duration() {
+        let words = cjk_words("一二三四五六七八九十");
+        // 每个字 0.2 秒，最长 1.1 秒
+        let groups = split_cues(&words, &params(42, 2, 1.1));
+        assert_eq!(joined(&groups), ["一二三四五", "六七八九十"]);
+    }
+
+    #[test]
+    fn test_wrap_lines_cjk_width() {
+        let words = cjk_words("一二三四五六七八九十");
+        let lines = wrap_lines(&words, &params(12, 2, 6.0));
+        assert_eq!(lines, ["一二三四五", "六七八九十"]);
+        assert!(lines.iter().all(|l| l.width() <= 12));
+    }
+
+    #[test]
+    fn test_wrap_lines_latin_spaces() {
+        let words: Vec<TimedWord> = ["hello", "big", "world"]
+            .iter()
+            .enumerate()
+            .map(|(i, text)| TimedWord {
+                text: text.to_string(),
+                start: i as f64,
+                end: i as f64 + 1.0,
+                space_before: i > 0,
+                confidence: None,
+            })
+            .collect();
+        assert_eq!(wrap_lines(&words, &params(42, 2, 6.0)), ["hello big world"]);
+        assert_eq!(
+            wrap_lines(&words, &params(10, 2, 6.0)),
+            ["hello", "big world"]
+        );
+    }
+
+    #[test]
+    fn test_cues_extend_short_and_avoid_overlap() {
+        let utterances = [
+            Utterance {
+                text: "好".to_string(),
+                start: 1.0,
+                end: 1.2,
+                ..Utterance::default()
+            },
+            Utterance {
+                text: "走".to_string(),
+                start: 1.5,
+                end: 1.7,
+                ..Utterance::default()
+            },
+        ];
+        let cues = cues(&utterances, &Config::default());
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].end, 1.5);
+        assert!((cues[1].end - 2.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_srt_and_vtt_format() {
+        let cues = [Cue {
+            start: 3661.5,
+            end: 3663.25,
+            lines: vec!["第一行".to_string(), "第二行".to_string()],
+        }];
+        assert_eq!(
+            to_srt(&cues),
+            "1\n01:01:01,500 --> 01:01:03,250\n第一行\n第二行\n\n"
+        );
+        assert_eq!(
+            to_vtt(&cues),
+            "WEBVTT\n\n01:01:01.500 --> 01:01:03.250\n第一行\n第二行\n\n"
+        );
+    }
+}
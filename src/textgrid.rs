@@ -0,0 +1,192 @@
+use crate::export;
+use crate::segmenter::Utterance;
+
+/// 标注层中的一个区间
+#[derive(Debug, Clone, PartialEq)]
+struct Interval {
+    xmin: f64,
+    xmax: f64,
+    text: String,
+}
+
+/// Praat TextGrid（长文本格式）
+///
+/// 包含两个区间层：`utterances`（每句一个区间）和 `words`（每个词一个区间）；
+/// 模型提供置信度时另加 `confidence` 层，区间与 `words` 相同，内容为词的
+/// 置信度。区间之间的空隙用空白区间填满，整个标注覆盖 0 到音频结束。
+pub fn to_textgrid(utterances: &[Utterance], duration: f64) -> String {
+    let words: Vec<_> = utterances.iter().flat_map(export::timed_words).collect();
+    let xmax = utterances
+        .iter()
+        .map(|u| u.end)
+        .chain(words.iter().map(|w| w.end))
+        .fold(duration, f64::max);
+
+    let mut tiers = vec![
+        (
+            "utterances",
+            intervals(
+                utterances.iter().map(|u| (u.start, u.end, u.text.clone())),
+                xmax,
+            ),
+        ),
+        (
+            "words",
+            intervals(words.iter().map(|w| (w.start, w.end, w.text.clone())), xmax),
+        ),
+    ];
+    if words.iter().any(|w| w.confidence.is_some()) {
+        let confidences = words.iter().map(|w| {
+            let text = w.confidence.map(|c| format!("{:.3}", c));
+            (w.start, w.end, text.unwrap_or_default())
+        });
+        tiers.push(("confidence", intervals(confidences, xmax)));
+    }
+
+    let mut out = String::new();
+    out.push_str("File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n\n");
+    out.push_str(&format!("xmin = 0\nxmax = {}\n", seconds(xmax)));
+    out.push_str(&format!(
+        "tiers? <exists>\nsize = {}\nitem []:\n",
+        tiers.len()
+    ));
+    for (i, (name, intervals)) in tiers.iter().enumerate() {
+        out.push_str(&format!("    item [{}]:\n", i + 1));
+        out.push_str("        class = \"IntervalTier\"\n");
+        out.push_str(&format!("        name = \"{}\"\n", name));
+        out.push_str(&format!(
+            "        xmin = 0\n        xmax = {}\n",
+            seconds(xmax)
+        ));
+        out.push_str(&format!("        intervals: size = {}\n", intervals.len()));
+        for (j, interval) in intervals.iter().enumerate() {
+            out.push_str(&format!(
+                "        intervals [{}]:\n            xmin = {}\n            xmax = {}\n            text = \"{}\"\n",
+                j + 1,
+                seconds(interval.xmin),
+                seconds(interval.xmax),
+                interval.text.replace('"', "\"\"")
+            ));
+        }
+    }
+    out
+}
+
+/// 把按时间排列的标注排成首尾相接、覆盖 `[0, xmax]` 的区间
+///
+/// Praat 要求区间长度为正且互不重叠：时间先取整到毫秒，开始时间早于上一个
+/// 区间结束时推后，推后后长度为零的标注并入上一个区间。
+fn intervals(spans: impl Iterator<Item = (f64, f64, String)>, xmax: f64) -> Vec<Interval> {
+    let xmax = millis(xmax);
+    let mut intervals: Vec<Interval> = Vec::new();
+    let mut cursor = 0.0;
+    for (start, end, text) in spans {
+        let start = millis(start).clamp(cursor, xmax);
+        let end = millis(end).min(xmax);
+        if end <= start {
+            if let Some(last) = intervals.last_mut() {
+                if !last.text.is_empty() && !text.is_empty() {
+                    last.text.push(' ');
+                }
+                last.text.push_str(&text);
+            }
+            continue;
+        }
+        if start > cursor {
+            intervals.push(Interval {
+                xmin: cursor,
+                xmax: start,
+                text: String::new(),
+            });
+        }
+        intervals.push(Interval {
+            xmin: start,
+            xmax: end,
+            text,
+        });
+        cursor = end;
+    }
+    if cursor < xmax || intervals.is_empty() {
+        intervals.push(Interval {
+            xmin: cursor,
+            xmax,
+            text: String::new(),
+        });
+    }
+    intervals
+}
+
+fn millis(seconds: f64) -> f64 {
+    (seconds.max(0.0) * 1000.0).round() / 1000.0
+}
+
+fn seconds(value: f64) -> String {
+    format!("{:.3}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: f64, end: f64, text: &str) -> (f64, f64, String) {
+        (start, end, text.to_string())
+    }
+
+    fn interval(xmin: f64, xmax: f64, text: &str) -> Interval {
+        Interval {
+            xmin,
+            xmax,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_intervals_fill_gaps() {
+        let spans = [span(0.5, 1.0, "你好"), span(1.5, 2.0, "再见")];
+        assert_eq!(
+            intervals(spans.into_iter(), 3.0),
+            [
+                interval(0.0, 0.5, ""),
+                interval(0.5, 1.0, "你好"),
+                interval(1.0, 1.5, ""),
+                interval(1.5, 2.0, "再见"),
+                interval(2.0, 3.0, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_intervals_clamp_overlap_and_merge_empty() {
+        let spans = [
+            span(0.0, 1.0, "a"),
+            span(0.8, 1.2, "b"),
+            span(1.2, 1.2, "c"),
+            span(1.0004, 1.2, "d"),
+        ];
+        assert_eq!(
+            intervals(spans.into_iter(), 1.2),
+            [interval(0.0, 1.0, "a"), interval(1.0, 1.2, "b c d")]
+        );
+        assert_eq!(intervals(std::iter::empty(), 2.0), [interval(0.0, 2.0, "")]);
+    }
+
+    #[test]
+    fn test_textgrid_tiers() {
+        let utterance = Utterance {
+            text: "say \"hi\"".to_string(),
+            tokens: vec!["▁SAY".to_string(), "▁HI".to_string()],
+            timestamps: vec![0.2, 0.6],
+            confidences: vec![0.9, 0.8],
+            start: 0.1,
+            end: 1.0,
+            ..Utterance::default()
+        };
+        let grid = to_textgrid(&[utterance], 2.0);
+        assert!(grid.starts_with("File type = \"ooTextFile\"\nObject class = \"TextGrid\"\n"));
+        assert!(grid.contains("size = 3\n"));
+        assert!(grid.contains("name = \"confidence\""));
+        assert!(grid.contains("text = \"say \"\"hi\"\"\""));
+        assert!(grid.contains("text = \"0.800\""));
+        assert!(grid.contains("xmax = 2.000\n"));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::audio::TARGET_SAMPLE_RATE;
+use crate::config::Config;
+use crate::endpoint::Endpointer;
+use crate::ffi::{OnlineRecognizer, OnlineStream};
+use crate::preprocess::Preprocessor;
+use crate::recorder::UtteranceRecorder;
+use crate::resampler::Resampler;
+use crate::segmenter::{Segmenter, SourceEvent, Utterance};
+use anyhow::Result;
+use std::path::Path;
+
+/// 输入结束时追加的静音（秒），让模型输出仍在前瞻窗口内的最后几帧
+const TAIL_PADDING_SECONDS: f32 = 0.66;
+
+/// 从混音后的音频到识别结果的处理链
+///
+/// 预处理 → 重采样 → AGC → VAD → 识别 → 断句。实时采集和文件转写共用，
+/// 每个实例拥有独立的 `OnlineStream` 和断句状态，共享同一个识别器。
+pub struct Transcriber {
+    name: String,
+    config: Config,
+    preprocessor: Preprocessor,
+    resampler: Option<Resampler>,
+    stream: OnlineStream,
+    endpointer: Endpointer,
+    recorder: Option<UtteranceRecorder>,
+    segmenter: Segmenter,
+    /// 已送入识别器的 16 kHz 样本数（不含尾部静音），用于计算语句的时间
+    stream_samples: u64,
+    /// 当前语句开始时的 `stream_samples`
+    utterance_start: u64,
+    verbose: bool,
+}
+
+impl Transcriber {
+    /// `sample_rate` 为输入音频的采样率；`name` 用于调试输出
+    pub fn new(
+        config: &Config,
+        recognizer: &OnlineRecognizer,
+        sample_rate: u32,
+        name: &str,
+        verbose: bool,
+    ) -> Result<Self> {
+        let mut endpointer = Endpointer::from_config(config, TARGET_SAMPLE_RATE)?;
+        if verbose || config.endpoint_log {
+            endpointer.enable_log(name);
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            config: config.clone(),
+            preprocessor: Preprocessor::new(config, sample_rate),
+            resampler: Resampler::new_if_needed(config.resampler, sample_rate, TARGET_SAMPLE_RATE),
+            stream: recognizer.create_stream(),
+            endpointer,
+            recorder: None,
+            segmenter: Segmenter::new(),
+            stream_samples: 0,
+            utterance_start: 0,
+            verbose,
+        })
+    }
+
+    /// 输入采样率变化（如重连到新设备）后重建预处理链和重采样器
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.preprocessor = Preprocessor::new(&self.config, sample_rate);
+        self.resampler =
+            Resampler::new_if_needed(self.config.resampler, sample_rate, TARGET_SAMPLE_RATE);
+    }
+
+    pub fn set_recorder(&mut self, recorder: UtteranceRecorder) {
+        self.recorder = Some(recorder);
+    }
+
+    pub fn recorder_mut(&mut self) -> Option<&mut UtteranceRecorder> {
+        self.recorder.as_mut()
+    }
+
+    pub fn preprocessor(&self) -> &Preprocessor {
+        &self.preprocessor
+    }
+
+    /// 处理一块输入音频，产生的识别事件追加到 `events`
+    pub fn accept(
+        &mut self,
+        recognizer: &OnlineRecognizer,
+        samples: Vec<f32>,
+        events: &mut Vec<SourceEvent>,
+    ) {
+        let samples = self.preprocessor.process(samples);
+        let mut samples = match self.resampler {
+            Some(ref mut r) => r.resample(&samples),
+            None => samples,
+        };
+        if samples.is_empty() {
+            return;
+        }
+
+        // 录音保存 AGC 之前的音频，用 `cinnabar transcribe` 回放时不会重复增益
+        let ungained = self.recorder.as_ref().map(|_| samples.clone());
+        // AGC 位于 VAD 之前，按上一帧的 VAD 判定决定是否调整增益
+        self.preprocessor
+            .apply_gain(&mut samples, self.endpointer.is_speech());
+        self.endpointer.accept_waveform(&samples);
+
+        if let (Some(recorder), Some(ungained)) = (&mut self.recorder, ungained) {
+            recorder.push(&ungained, self.endpointer.utterance_seconds() > 0.0);
+        }
+
+        self.stream
+            .accept_waveform(TARGET_SAMPLE_RATE as i32, &samples);
+        self.stream_samples += samples.len() as u64;
+        while recognizer.is_ready(&self.stream) {
+            recognizer.decode(&mut self.stream);
+        }
+
+        let result = recognizer.get_result(&self.stream);
+        self.segmenter.update(&result, events);
+
+        let stream = &self.stream;
+        if let Some(reason) = self.endpointer.check(|| recognizer.is_endpoint(stream)) {
+            self.end_utterance(recognizer, Some(reason.to_string()), events);
+        }
+    }
+
+    /// 输入结束（Ctrl+C 或文件结尾）：追加尾部静音并通知模型输入已结束，
+    /// 解码剩余的帧并输出最后一句
+    pub fn finish(&mut self, recognizer: &OnlineRecognizer, events: &mut Vec<SourceEvent>) {
+        let padding = vec![0.0; (TARGET_SAMPLE_RATE as f32 * TAIL_PADDING_SECONDS) as usize];
+        self.stream
+            .accept_waveform(TARGET_SAMPLE_RATE as i32, &padding);
+        self.stream.input_finished();
+        while recognizer.is_ready(&self.stream) {
+            recognizer.decode(&mut self.stream);
+        }
+
+        self.end_utterance(recognizer, None, events);
+    }
+
+    /// 结束当前语句：确认剩余文本，输出整句结果，保存录音并重置识别流
+    fn end_utterance(
+        &mut self,
+        recognizer: &OnlineRecognizer,
+        reason: Option<String>,
+        events: &mut Vec<SourceEvent>,
+    ) {
+        let result = recognizer.get_detailed_result(&self.stream);
+        let text = result.text.trim();
+        if self.verbose {
+            eprintln!(
+                "[DEBUG] {}: endpoint，最终结果长度 = {}",
+                self.name,
+                text.len()
+            );
+        }
+        self.segmenter.end_utterance(text, events);
+
+        let rate = TARGET_SAMPLE_RATE as f64;
+        let stream_start = self.utterance_start as f64 / rate;
+        let stream_end = self.stream_samples as f64 / rate;
+        // VAD 检测到的语音范围；没有检测到语音时取整段
+        let speech_seconds = self.endpointer.utterance_seconds() as f64;
+        let (start, end) = if speech_seconds > 0.0 {
+            let silence = self.endpointer.stats().trailing_silence_seconds as f64;
+            (
+                (stream_end - speech_seconds).max(stream_start),
+                (stream_end - silence).max(stream_start),
+            )
+        } else {
+            (stream_start, stream_end)
+        };
+        if !text.is_empty() {
+            events.push(SourceEvent::Endpoint(Utterance {
+                text: text.to_string(),
+                tokens: result.tokens,
+                timestamps: result
+                    .timestamps
+                    .iter()
+                    .map(|t| (stream_start + *t as f64) as f32)
+                    .collect(),
+                confidences: result.confidences,
+                start,
+                end,
+                reason,
+            }));
+        }
+
+        self.save_recording(text, events);
+        recognizer.reset(&mut self.stream);
+        self.endpointer.reset();
+        self.utterance_start = self.stream_samples;
+    }
+
+    fn save_recording(&mut self, text: &str, events: &mut Vec<SourceEvent>) {
+        let Some(ref mut recorder) = self.recorder else {
+            return;
+        };
+        match recorder.finish(text, self.endpointer.stats()) {
+            Ok(Some(path)) if self.verbose => {
+                eprintln!("[DEBUG] {}: 已保存录音 {}", self.name, path.display());
+            }
+            Ok(_) => {}
+            Err(e) => events.push(SourceEvent::Error(format!("保存录音失败: {:#}", e))),
+        }
+    }
+}
+
+/// 按固定块长把整段音频送入处理链，模拟实时输入；结束时冲刷最后一句
+pub fn transcribe_samples(
+    transcriber: &mut Transcriber,
+    recognizer: &OnlineRecognizer,
+    samples: &[f32],
+    sample_rate: u32,
+    events: &mut Vec<SourceEvent>,
+) {
+    // 100 ms 一块，与实时采集的块长相当，断句行为与实时识别一致
+    let chunk = (sample_rate as usize / 10).max(1);
+    for block in samples.chunks(chunk) {
+        transcriber.accept(recognizer, block.to_vec(), events);
+    }
+    transcriber.finish(recognizer, events);
+}
+
+/// 一个音频文件的转写结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transcript {
+    /// 每一句的识别结果
+    pub utterances: Vec<Utterance>,
+    /// 音频总时长（秒）
+    pub duration: f64,
+}
+
+/// 转写 WAV 文件
+pub fn transcribe_file(
+    config: &Config,
+    recognizer: &OnlineRecognizer,
+    path: &Path,
+    verbose: bool,
+) -> Result<Transcript> {
+    let (samples, sample_rate) = crate::wav::read_wav(path)?;
+    let name = path.display().to_string();
+    let mut transcriber = Transcriber::new(config, recognizer, sample_rate, &name, verbose)?;
+
+    let mut events = Vec::new();
+    transcribe_samples(
+        &mut transcriber,
+        recognizer,
+        &samples,
+        sample_rate,
+        &mut events,
+    );
+    let utterances = events
+        .into_iter()
+        .filter_map(|event| match event {
+            SourceEvent::Endpoint(utterance) => Some(utterance),
+            _ => None,
+        })
+        .collect();
+    Ok(Transcript {
+        utterances,
+        duration: samples.len() as f64 / sample_rate.max(1) as f64,
+    })
+}
@@ -1,67 +1,480 @@
+use crate::config::Config;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 自适应模式下噪声底下降（跟随更安静的环境）的时间常数（秒）
+const NOISE_FALL_SECONDS: f32 = 0.2;
+/// 非语音块高于噪声底时，噪声底上升的时间常数（秒）
+const NOISE_RISE_SECONDS: f32 = 3.0;
+/// 语音块期间噪声底的缓慢爬升速度（dB/秒），环境持续变吵时阈值最终能跟上
+const NOISE_CREEP_DB_PER_SECOND: f32 = 0.5;
+/// 自适应阈值下限，避免数字静音时阈值趋近于零
+const MIN_ADAPTIVE_THRESHOLD: f32 = 1e-7;
+
 pub struct VadDetector {
     threshold: f32,
+    adaptive: Option<NoiseTracker>,
+}
+
+/// 噪声底跟踪：快速下降、缓慢上升，阈值保持在噪声底之上固定余量
+struct NoiseTracker {
+    sample_rate: u32,
+    noise_floor: f32,
+    margin: f32,
 }
 
 impl VadDetector {
     pub fn new(threshold: f32) -> Self {
-        Self { threshold }
+        Self {
+            threshold,
+            adaptive: None,
+        }
+    }
+
+    /// 自适应阈值：从 `threshold` 出发跟踪噪声底，阈值始终为噪声底加 `margin_db`
+    pub fn adaptive(threshold: f32, margin_db: f32, sample_rate: u32) -> Self {
+        let margin = 10f32.powf(margin_db / 10.0);
+        Self {
+            threshold,
+            adaptive: Some(NoiseTracker {
+                sample_rate,
+                noise_floor: threshold / margin,
+                margin,
+            }),
+        }
+    }
+
+    /// 按配置创建：`vad_adaptive` 启用时使用自适应阈值
+    pub fn from_config(config: &Config, sample_rate: u32) -> Self {
+        if config.vad_adaptive {
+            Self::adaptive(config.vad_threshold, config.vad_margin_db, sample_rate)
+        } else {
+            Self::new(config.vad_threshold)
+        }
+    }
+
+    /// 判断是否为语音；自适应模式下同时更新噪声底和阈值
+    pub fn detect(&mut self, samples: &[f32]) -> bool {
+        if samples.is_empty() {
+            return false;
+        }
+        let energy = Self::energy(samples);
+        let is_speech = energy > self.threshold;
+        if let Some(tracker) = &mut self.adaptive {
+            tracker.update(energy, samples.len(), is_speech);
+            self.threshold = (tracker.noise_floor * tracker.margin).max(MIN_ADAPTIVE_THRESHOLD);
+        }
+        is_speech
+    }
+
+    /// 计算均方能量
+    pub fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32
     }
 
-    pub fn is_speech(&self, samples: &[f32]) -> bool {
-        let energy: f32 = samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32;
-        energy > self.threshold
+    pub fn threshold(&self) -> f32 {
+        self.threshold
     }
 }
 
-pub struct EndpointDetector {
+impl NoiseTracker {
+    fn update(&mut self, energy: f32, len: usize, is_speech: bool) {
+        let dt = len as f32 / self.sample_rate as f32;
+        if energy < self.noise_floor {
+            self.noise_floor += (energy - self.noise_floor) * smoothing(dt, NOISE_FALL_SECONDS);
+        } else if !is_speech {
+            self.noise_floor += (energy - self.noise_floor) * smoothing(dt, NOISE_RISE_SECONDS);
+        } else {
+            let creep = 10f32.powf(NOISE_CREEP_DB_PER_SECOND * dt / 10.0);
+            self.noise_floor = (self.noise_floor * creep).min(energy);
+        }
+    }
+}
+
+/// 时长为 `dt` 的块对应的一阶平滑系数
+fn smoothing(dt: f32, time_constant: f32) -> f32 {
+    1.0 - (-dt / time_constant).exp()
+}
+
+/// 帧级 VAD 参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadParams {
+    /// 分析帧长（毫秒），限制在 10–30 ms
+    pub frame_ms: u32,
+    /// 语音结束阈值比开始阈值低多少（dB），避免在阈值附近来回切换
+    pub hysteresis_db: f32,
+    /// 连续超过开始阈值多长时间才判为语音开始（毫秒），滤除单个咔嗒声
+    pub min_speech_ms: u32,
+    /// 低于结束阈值后仍保持语音状态的时长（毫秒），跨过词间短暂停顿
+    pub hangover_ms: u32,
+}
+
+impl Default for VadParams {
+    fn default() -> Self {
+        Self {
+            frame_ms: 20,
+            hysteresis_db: 3.0,
+            min_speech_ms: 60,
+            hangover_ms: 100,
+        }
+    }
+}
+
+impl VadParams {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            frame_ms: config.vad_frame_ms,
+            hysteresis_db: config.vad_hysteresis_db,
+            min_speech_ms: config.vad_min_speech_ms,
+            hangover_ms: config.vad_hangover_ms,
+        }
+    }
+}
+
+/// 单帧的判定结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadFrame {
+    pub energy: f32,
+    pub is_speech: bool,
+    /// 本帧确认语音开始时，之前暂判为静音、实际属于这段语音的帧数
+    pub onset_frames: u32,
+}
+
+/// 固定帧长的 VAD 状态机
+///
+/// 输入块先拼接成固定长度的帧再判定，结果与设备回调的块大小无关。
+/// 静音状态下连续 `min_speech_ms` 超过开始阈值才进入语音状态；
+/// 语音状态下低于结束阈值（开始阈值减 `hysteresis_db`）持续超过 `hangover_ms` 才回到静音。
+pub struct FrameVad {
     vad: VadDetector,
     sample_rate: u32,
-    min_silence_duration: f32,
-    min_speech_duration: f32,
+    frame_len: usize,
+    offset_ratio: f32,
+    min_speech_frames: u32,
+    hangover_frames: u32,
+    pending: Vec<f32>,
+    in_speech: bool,
+    onset_run: u32,
+    hangover_left: u32,
+}
+
+impl FrameVad {
+    pub fn new(vad: VadDetector, sample_rate: u32, params: VadParams) -> Self {
+        let frame_ms = params.frame_ms.clamp(10, 30);
+        let frame_len = ((sample_rate as u64 * frame_ms as u64 / 1000) as usize).max(1);
+        let frames = |ms: u32| ms.div_ceil(frame_ms);
+        Self {
+            vad,
+            sample_rate,
+            frame_len,
+            offset_ratio: 10f32.powf(-params.hysteresis_db.max(0.0) / 10.0),
+            min_speech_frames: frames(params.min_speech_ms).max(1),
+            hangover_frames: frames(params.hangover_ms),
+            pending: Vec::with_capacity(frame_len),
+            in_speech: false,
+            onset_run: 0,
+            hangover_left: 0,
+        }
+    }
+
+    /// 按配置创建（阈值、自适应和帧参数）
+    pub fn from_config(config: &Config, sample_rate: u32) -> Self {
+        Self::new(
+            VadDetector::from_config(config, sample_rate),
+            sample_rate,
+            VadParams::from_config(config),
+        )
+    }
+
+    /// 处理一个音频块，对其中每个完整的帧调用 `on_frame`；不足一帧的样本留到下次
+    pub fn process(&mut self, samples: &[f32], mut on_frame: impl FnMut(&[f32], VadFrame)) {
+        let mut samples = samples;
+        if !self.pending.is_empty() {
+            let take = (self.frame_len - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..take]);
+            samples = &samples[take..];
+            if self.pending.len() < self.frame_len {
+                return;
+            }
+            let frame = std::mem::take(&mut self.pending);
+            let decision = self.classify(&frame);
+            on_frame(&frame, decision);
+            self.pending = frame;
+            self.pending.clear();
+        }
+
+        let mut frames = samples.chunks_exact(self.frame_len);
+        for frame in &mut frames {
+            let decision = self.classify(frame);
+            on_frame(frame, decision);
+        }
+        self.pending.extend_from_slice(frames.remainder());
+    }
+
+    fn classify(&mut self, frame: &[f32]) -> VadFrame {
+        let energy = VadDetector::energy(frame);
+        let above_onset = self.vad.detect(frame);
+        let mut onset_frames = 0;
+
+        if self.in_speech {
+            if energy > self.vad.threshold() * self.offset_ratio {
+                self.hangover_left = self.hangover_frames;
+            } else if self.hangover_left > 0 {
+                self.hangover_left -= 1;
+            } else {
+                self.in_speech = false;
+            }
+        } else if above_onset {
+            self.onset_run += 1;
+            if self.onset_run >= self.min_speech_frames {
+                self.in_speech = true;
+                self.hangover_left = self.hangover_frames;
+                onset_frames = self.onset_run - 1;
+                self.onset_run = 0;
+            }
+        } else {
+            self.onset_run = 0;
+        }
+
+        VadFrame {
+            energy,
+            is_speech: self.in_speech,
+            onset_frames,
+        }
+    }
+
+    /// 当前的语音开始阈值
+    pub fn threshold(&self) -> f32 {
+        self.vad.threshold()
+    }
+
+    /// 最近一个完整帧是否判为语音（含拖尾期）
+    pub fn is_speech(&self) -> bool {
+        self.in_speech
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// 带预录缓冲的语音门限
+///
+/// 非语音帧不会直接丢弃，而是保存在预录环形缓冲中（最近 `pre_roll_ms`）；
+/// 检测到语音时先输出缓冲内容，避免语句开头较轻的音节被截掉。
+/// 句尾的拖尾由 [`FrameVad`] 的 `hangover_ms` 负责：拖尾期内的帧仍判为语音并放行，
+/// 保留句尾的弱辅音和尾音。
+pub struct SpeechGate {
+    vad: FrameVad,
+    pre_roll: VecDeque<f32>,
+    pre_roll_capacity: usize,
+}
+
+impl SpeechGate {
+    pub fn new(vad: FrameVad, pre_roll_ms: u32) -> Self {
+        let sample_rate = vad.sample_rate() as u64;
+        let pre_roll_capacity = (sample_rate * pre_roll_ms as u64 / 1000) as usize;
+        Self {
+            vad,
+            pre_roll: VecDeque::with_capacity(pre_roll_capacity),
+            pre_roll_capacity,
+        }
+    }
+
+    /// 处理一个音频块，返回应送入识别器的样本；门限关闭时返回空
+    pub fn process(&mut self, samples: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        let Self {
+            vad,
+            pre_roll,
+            pre_roll_capacity,
+        } = self;
+
+        vad.process(samples, |frame, decision| {
+            if decision.is_speech {
+                output.extend(pre_roll.drain(..));
+                output.extend_from_slice(frame);
+            } else {
+                buffer(pre_roll, *pre_roll_capacity, frame);
+            }
+        });
+        output
+    }
+}
+
+/// 将样本放入预录缓冲，只保留最近 `capacity` 个
+fn buffer(pre_roll: &mut VecDeque<f32>, capacity: usize, samples: &[f32]) {
+    if capacity == 0 {
+        return;
+    }
+    let samples = &samples[samples.len().saturating_sub(capacity)..];
+    let overflow = (pre_roll.len() + samples.len()).saturating_sub(capacity);
+    pre_roll.drain(..overflow);
+    pre_roll.extend(samples);
+}
+
+/// 单个语句的 VAD 统计信息
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct VadStats {
+    pub threshold: f32,
+    pub speech_seconds: f32,
+    pub trailing_silence_seconds: f32,
+    pub speech_frames: u32,
+    pub silence_frames: u32,
+    pub peak_energy: f32,
+}
+
+/// 断句参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EndpointParams {
+    /// 语音后连续静音达到该时长（毫秒）即断句
+    pub min_silence_ms: u32,
+    /// 语音累计达到该时长（毫秒）才允许按静音断句，避免把咳嗽等短促声音当作一句
+    pub min_speech_ms: u32,
+    /// 语句最长时长（毫秒），超过后强制断句；0 表示不限制
+    pub max_utterance_ms: u32,
+}
+
+impl Default for EndpointParams {
+    fn default() -> Self {
+        Self {
+            min_silence_ms: 1200,
+            min_speech_ms: 500,
+            max_utterance_ms: 20000,
+        }
+    }
+}
+
+impl EndpointParams {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            min_silence_ms: config.endpoint_min_silence_ms,
+            min_speech_ms: config.endpoint_min_speech_ms,
+            max_utterance_ms: config.endpoint_max_utterance_ms,
+        }
+    }
+}
+
+pub struct EndpointDetector {
+    vad: FrameVad,
+    sample_rate: u32,
+    min_silence_samples: u64,
+    min_speech_samples: u64,
+    max_utterance_samples: Option<u64>,
     silence_samples: u32,
     speech_samples: u32,
+    utterance_samples: u64,
+    speech_frames: u32,
+    silence_frames: u32,
+    peak_energy: f32,
 }
 
 impl EndpointDetector {
-    pub fn new(
-        vad_threshold: f32,
-        sample_rate: u32,
-        min_silence_duration: f32,
-        min_speech_duration: f32,
-    ) -> Self {
+    pub fn new(vad: FrameVad, params: EndpointParams) -> Self {
+        let sample_rate = vad.sample_rate();
+        let samples = |ms: u32| sample_rate as u64 * ms as u64 / 1000;
         Self {
-            vad: VadDetector::new(vad_threshold),
             sample_rate,
-            min_silence_duration,
-            min_speech_duration,
+            vad,
+            min_silence_samples: samples(params.min_silence_ms),
+            min_speech_samples: samples(params.min_speech_ms),
+            max_utterance_samples: (params.max_utterance_ms > 0)
+                .then(|| samples(params.max_utterance_ms)),
             silence_samples: 0,
             speech_samples: 0,
+            utterance_samples: 0,
+            speech_frames: 0,
+            silence_frames: 0,
+            peak_energy: 0.0,
         }
     }
 
+    /// 按配置创建（VAD 和断句参数）
+    pub fn from_config(config: &Config, sample_rate: u32) -> Self {
+        Self::new(
+            FrameVad::from_config(config, sample_rate),
+            EndpointParams::from_config(config),
+        )
+    }
+
     pub fn accept_waveform(&mut self, samples: &[f32]) -> bool {
-        let is_speech = self.vad.is_speech(samples);
+        let Self {
+            vad,
+            silence_samples,
+            speech_samples,
+            utterance_samples,
+            speech_frames,
+            silence_frames,
+            peak_energy,
+            ..
+        } = self;
 
-        if is_speech {
-            self.speech_samples += samples.len() as u32;
-            self.silence_samples = 0;
-        } else {
-            self.silence_samples += samples.len() as u32;
-        }
+        vad.process(samples, |frame, decision| {
+            *peak_energy = peak_energy.max(decision.energy);
+            let len = frame.len() as u32;
+            if decision.is_speech {
+                // 语音开始确认前暂计为静音的帧补记为语音；句中恢复说话时
+                // 这些帧已作为停顿计入语句时长，不再重复累加
+                let onset = decision.onset_frames.min(*silence_frames);
+                let counted = if *speech_samples > 0 { 0 } else { onset };
+                *speech_samples += len * (onset + 1);
+                *utterance_samples += (len * (counted + 1)) as u64;
+                *speech_frames += onset + 1;
+                *silence_frames -= onset;
+                *silence_samples = 0;
+            } else {
+                *silence_samples += len;
+                *silence_frames += 1;
+                // 语句从第一个语音帧开始计时，句中停顿也计入时长
+                if *speech_samples > 0 {
+                    *utterance_samples += len as u64;
+                }
+            }
+        });
 
         self.is_endpoint()
     }
 
     pub fn is_endpoint(&self) -> bool {
-        let silence_duration = self.silence_samples as f32 / self.sample_rate as f32;
-        let speech_duration = self.speech_samples as f32 / self.sample_rate as f32;
+        let silence_ended = self.speech_samples as u64 >= self.min_speech_samples
+            && self.silence_samples as u64 >= self.min_silence_samples;
+        silence_ended || self.is_forced_cut()
+    }
 
-        speech_duration >= self.min_speech_duration && silence_duration >= self.min_silence_duration
+    /// 最近一个完整帧是否判为语音
+    pub fn is_speech(&self) -> bool {
+        self.vad.is_speech()
+    }
+
+    /// 从第一个语音帧开始的语句时长（秒）
+    pub fn utterance_seconds(&self) -> f32 {
+        self.utterance_samples as f32 / self.sample_rate as f32
+    }
+
+    /// 语句是否已达到最长时长（需要强制断句）
+    pub fn is_forced_cut(&self) -> bool {
+        self.max_utterance_samples
+            .is_some_and(|max| self.utterance_samples >= max)
+    }
+
+    pub fn stats(&self) -> VadStats {
+        VadStats {
+            threshold: self.vad.threshold(),
+            speech_seconds: self.speech_samples as f32 / self.sample_rate as f32,
+            trailing_silence_seconds: self.silence_samples as f32 / self.sample_rate as f32,
+            speech_frames: self.speech_frames,
+            silence_frames: self.silence_frames,
+            peak_energy: self.peak_energy,
+        }
     }
 
     pub fn reset(&mut self) {
         self.silence_samples = 0;
         self.speech_samples = 0;
+        self.utterance_samples = 0;
+        self.speech_frames = 0;
+        self.silence_frames = 0;
+        self.peak_energy = 0.0;
     }
 }
 
@@ -71,28 +484,58 @@ mod tests {
 
     #[test]
     fn test_vad_silence() {
-        let vad = VadDetector::new(0.01);
+        let mut vad = VadDetector::new(0.01);
         let silence = vec![0.0; 100];
-        assert!(!vad.is_speech(&silence));
+        assert!(!vad.detect(&silence));
     }
 
     #[test]
     fn test_vad_speech() {
-        let vad = VadDetector::new(0.01);
+        let mut vad = VadDetector::new(0.01);
         let speech: Vec<f32> = (0..100).map(|i| (i as f32 * 0.1).sin()).collect();
-        assert!(vad.is_speech(&speech));
+        assert!(vad.detect(&speech));
     }
 
     #[test]
     fn test_vad_threshold() {
-        let vad = VadDetector::new(0.5);
+        let mut vad = VadDetector::new(0.5);
         let low_energy = vec![0.1; 100];
-        assert!(!vad.is_speech(&low_energy));
+        assert!(!vad.detect(&low_energy));
+    }
+
+    /// 20 ms（320 个样本）一帧、逐帧判定的 VAD，便于按样本数核对
+    fn frame_vad(threshold: f32) -> FrameVad {
+        frame_vad_with_hangover(threshold, 0)
+    }
+
+    fn frame_vad_with_hangover(threshold: f32, hangover_ms: u32) -> FrameVad {
+        FrameVad::new(
+            VadDetector::new(threshold),
+            16000,
+            VadParams {
+                frame_ms: 20,
+                hysteresis_db: 0.0,
+                min_speech_ms: 20,
+                hangover_ms,
+            },
+        )
+    }
+
+    fn endpoint_params(
+        min_silence_ms: u32,
+        min_speech_ms: u32,
+        max_utterance_ms: u32,
+    ) -> EndpointParams {
+        EndpointParams {
+            min_silence_ms,
+            min_speech_ms,
+            max_utterance_ms,
+        }
     }
 
     #[test]
     fn test_endpoint_detector() {
-        let mut detector = EndpointDetector::new(0.01, 16000, 1.0, 0.5);
+        let mut detector = EndpointDetector::new(frame_vad(0.01), endpoint_params(1000, 500, 0));
 
         let speech: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.1).sin()).collect();
         assert!(!detector.accept_waveform(&speech));
@@ -103,10 +546,231 @@ mod tests {
 
     #[test]
     fn test_endpoint_reset() {
-        let mut detector = EndpointDetector::new(0.01, 16000, 1.0, 0.5);
+        let mut detector = EndpointDetector::new(frame_vad(0.01), endpoint_params(1000, 500, 0));
         let speech: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.1).sin()).collect();
         detector.accept_waveform(&speech);
         detector.reset();
         assert!(!detector.is_endpoint());
     }
+
+    #[test]
+    fn test_endpoint_stats() {
+        let mut detector = EndpointDetector::new(frame_vad(0.01), endpoint_params(1000, 500, 0));
+        let speech: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.1).sin()).collect();
+        detector.accept_waveform(&speech);
+        detector.accept_waveform(&vec![0.0; 4000]);
+
+        let stats = detector.stats();
+        assert_eq!(stats.speech_frames, 25);
+        // 4000 个样本中完整的 12 帧，剩余 160 个样本等待下一块
+        assert_eq!(stats.silence_frames, 12);
+        assert_eq!(stats.speech_seconds, 0.5);
+        assert_eq!(stats.trailing_silence_seconds, 0.24);
+        assert!(stats.peak_energy > 0.01);
+
+        detector.reset();
+        assert_eq!(detector.stats().speech_frames, 0);
+    }
+
+    #[test]
+    fn test_endpoint_ignores_click() {
+        let vad = FrameVad::new(VadDetector::new(0.01), 16000, VadParams::default());
+        let mut detector = EndpointDetector::new(vad, endpoint_params(1000, 500, 0));
+        detector.accept_waveform(&speech(8000));
+
+        // 0.6 秒静音中间夹一个 5 ms 的咔嗒声，不应把静音计时清零
+        detector.accept_waveform(&vec![0.0; 9600]);
+        assert!(!detector.accept_waveform(&vec![0.9; 80]));
+        assert!(detector.accept_waveform(&vec![0.0; 9600]));
+    }
+
+    #[test]
+    fn test_endpoint_max_utterance_forces_cut() {
+        let mut detector = EndpointDetector::new(frame_vad(0.01), endpoint_params(1000, 500, 2000));
+        // 连续说话，句中停顿 0.5 秒，不满足静音断句条件
+        assert!(!detector.accept_waveform(&speech(16000)));
+        assert!(!detector.accept_waveform(&vec![0.0; 8000]));
+        assert!(!detector.is_forced_cut());
+        assert!(detector.accept_waveform(&speech(8000)));
+        assert!(detector.is_forced_cut());
+
+        detector.reset();
+        assert!(!detector.accept_waveform(&speech(16000)));
+    }
+
+    #[test]
+    fn test_endpoint_utterance_seconds_with_pauses() {
+        // 最短语音 60 ms = 3 帧，每次恢复说话都有 2 帧待确认
+        let vad = FrameVad::new(
+            VadDetector::new(0.01),
+            16000,
+            VadParams {
+                frame_ms: 20,
+                hysteresis_db: 0.0,
+                min_speech_ms: 60,
+                hangover_ms: 0,
+            },
+        );
+        let mut detector = EndpointDetector::new(vad, endpoint_params(1000, 500, 0));
+        detector.accept_waveform(&speech(960));
+        assert_eq!(detector.utterance_seconds(), 0.06);
+        for _ in 0..2 {
+            detector.accept_waveform(&vec![0.0; 640]);
+            detector.accept_waveform(&speech(960));
+        }
+        assert_eq!(detector.utterance_seconds(), 0.26);
+        assert_eq!(detector.stats().speech_seconds, 0.18);
+    }
+
+    #[test]
+    fn test_endpoint_without_max_utterance() {
+        let mut detector = EndpointDetector::new(frame_vad(0.01), endpoint_params(1000, 500, 0));
+        for _ in 0..60 {
+            assert!(!detector.accept_waveform(&speech(16000)));
+        }
+    }
+
+    #[test]
+    fn test_frame_vad_independent_of_chunk_size() {
+        let mut signal = vec![0.0; 4800];
+        signal.extend(speech(8000));
+        signal.extend(vec![0.0; 8000]);
+
+        let decisions = |chunk: usize| {
+            let mut vad = FrameVad::new(VadDetector::new(0.01), 16000, VadParams::default());
+            let mut decisions = Vec::new();
+            for block in signal.chunks(chunk) {
+                vad.process(block, |_, decision| decisions.push(decision.is_speech));
+            }
+            decisions
+        };
+        let small = decisions(37);
+        assert_eq!(small, decisions(4096));
+        assert_eq!(small.len(), signal.len() / 320);
+    }
+
+    #[test]
+    fn test_frame_vad_min_speech_run() {
+        // 最短语音 60 ms = 3 帧
+        let mut vad = FrameVad::new(VadDetector::new(0.01), 16000, VadParams::default());
+        let mut decisions = Vec::new();
+        vad.process(&speech(960), |_, decision| decisions.push(decision));
+        assert!(!decisions[0].is_speech && !decisions[1].is_speech);
+        assert!(decisions[2].is_speech);
+        assert_eq!(decisions[2].onset_frames, 2);
+    }
+
+    #[test]
+    fn test_frame_vad_hysteresis_and_hangover() {
+        let params = VadParams {
+            frame_ms: 20,
+            hysteresis_db: 6.0,
+            min_speech_ms: 20,
+            hangover_ms: 40,
+        };
+        let mut vad = FrameVad::new(VadDetector::new(0.01), 16000, params);
+        let last = |vad: &mut FrameVad, samples: &[f32]| {
+            let mut is_speech = false;
+            vad.process(samples, |_, decision| is_speech = decision.is_speech);
+            is_speech
+        };
+        assert!(last(&mut vad, &speech(320)));
+
+        // 能量 0.005：低于开始阈值，但高于结束阈值（约 0.0025），保持语音状态
+        assert!(last(&mut vad, &vec![0.0707; 3200]));
+
+        // 静音 2 帧仍在拖尾期内，第 3 帧回到静音
+        assert!(last(&mut vad, &vec![0.0; 640]));
+        assert!(vad.is_speech());
+        assert!(!last(&mut vad, &vec![0.0; 320]));
+        assert!(!vad.is_speech());
+    }
+
+    fn speech(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.1).sin()).collect()
+    }
+
+    #[test]
+    fn test_gate_pre_roll() {
+        // 预录 100 ms = 1600 个样本
+        let mut gate = SpeechGate::new(frame_vad(0.01), 100);
+        for i in 0..4 {
+            let quiet = vec![0.001 * (i + 1) as f32; 800];
+            assert!(gate.process(&quiet).is_empty());
+        }
+
+        // 800 个样本中完整的 2 帧通过门限，剩余 160 个等待凑满一帧
+        let output = gate.process(&speech(800));
+        assert_eq!(output.len(), 1600 + 640);
+        // 只保留最近的 1600 个样本
+        assert_eq!(output[0], 0.003);
+        assert_eq!(output[1599], 0.004);
+
+        // 缓冲已输出，不会重复送入
+        assert_eq!(gate.process(&speech(800)).len(), 960);
+    }
+
+    #[test]
+    fn test_gate_hangover() {
+        // VAD 拖尾 100 ms = 5 帧（1600 个样本）
+        let mut gate = SpeechGate::new(frame_vad_with_hangover(0.01, 100), 0);
+        assert_eq!(gate.process(&speech(960)).len(), 960);
+
+        assert_eq!(gate.process(&vec![0.0; 1280]).len(), 1280);
+        assert_eq!(gate.process(&vec![0.0; 1280]).len(), 320);
+        assert!(gate.process(&vec![0.0; 1280]).is_empty());
+    }
+
+    #[test]
+    fn test_gate_silence_after_hangover_goes_to_pre_roll() {
+        let mut gate = SpeechGate::new(frame_vad_with_hangover(0.01, 40), 100);
+        gate.process(&speech(640));
+        // 拖尾放行 2 帧，之后的 2 帧进入预录缓冲
+        assert_eq!(gate.process(&vec![0.0; 1280]).len(), 640);
+        assert_eq!(gate.process(&speech(320)).len(), 640 + 320);
+    }
+
+    #[test]
+    fn test_adaptive_threshold_tracks_noise_floor() {
+        // 初始阈值 0.01，余量 10 dB；环境噪声能量约 1e-4
+        let mut vad = VadDetector::adaptive(0.01, 10.0, 16000);
+        let noise = vec![0.01; 160];
+        for _ in 0..100 {
+            assert!(!vad.detect(&noise));
+        }
+        // 阈值 = 噪声底 × 10 dB
+        assert!((vad.threshold() - 1e-3).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_adaptive_threshold_rises_with_noise() {
+        let mut vad = VadDetector::adaptive(0.001, 10.0, 16000);
+        // 噪声变大但仍低于阈值：噪声底按上升时间常数缓慢跟随
+        let noise = vec![0.02; 160];
+        for _ in 0..1000 {
+            vad.detect(&noise);
+        }
+        assert!(vad.threshold() > 0.003);
+        assert!(!vad.detect(&noise));
+    }
+
+    #[test]
+    fn test_adaptive_threshold_ignores_speech() {
+        let mut vad = VadDetector::adaptive(0.01, 10.0, 16000);
+        let before = vad.threshold();
+        // 1 秒语音只让噪声底爬升约 0.5 dB
+        for _ in 0..100 {
+            assert!(vad.detect(&speech(160)));
+        }
+        assert!(vad.threshold() < before * 1.15);
+    }
+
+    #[test]
+    fn test_fixed_threshold_does_not_adapt() {
+        let mut vad = VadDetector::new(0.01);
+        for _ in 0..100 {
+            vad.detect(&vec![0.001; 160]);
+        }
+        assert_eq!(vad.threshold(), 0.01);
+    }
 }
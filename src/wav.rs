@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// 将单声道 f32 样本写入 32 位浮点 WAV 文件
+///
+/// 使用 IEEE float 格式而不是 16 位 PCM，保证写出的数据与
+/// 送入识别器的样本逐位一致，便于复现识别问题。
+pub fn write_wav(path: &Path, samples: &[f32], sample_rate: u32) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("无法创建 WAV 文件: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    let channels: u16 = 1;
+    let bits_per_sample: u16 = 32;
+    let block_align = channels * bits_per_sample / 8;
+    let byte_rate = sample_rate * block_align as u32;
+    let data_len = (samples.len() * block_align as usize) as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // 3 = WAVE_FORMAT_IEEE_FLOAT
+    writer.write_all(&3u16.to_le_bytes())?;
+    writer.write_all(&channels.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&bits_per_sample.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// 读取 WAV 文件，返回单声道 f32 样本和采样率
+///
+/// 支持 8/16/24/32 位 PCM 和 32 位浮点；多声道取平均合并为单声道。
+pub fn read_wav(path: &Path) -> Result<(Vec<f32>, u32)> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("无法读取 WAV 文件: {}", path.display()))?;
+    parse_wav(&bytes).with_context(|| format!("无效的 WAV 文件: {}", path.display()))
+}
+
+struct WavFormat {
+    format_tag: u16,
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+}
+
+fn parse_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32)> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        anyhow::bail!("缺少 RIFF/WAVE 文件头");
+    }
+
+    let mut format = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = u32::from_le_bytes(bytes[pos + 4..pos + 8].try_into()?) as usize;
+        let body = &bytes[pos + 8..(pos + 8 + len).min(bytes.len())];
+
+        match id {
+            b"fmt " => {
+                if body.len() < 16 {
+                    anyhow::bail!("fmt 块过短");
+                }
+                let mut format_tag = u16::from_le_bytes(body[0..2].try_into()?);
+                // WAVE_FORMAT_EXTENSIBLE：实际格式在子格式 GUID 的前两个字节
+                if format_tag == 0xFFFE && body.len() >= 26 {
+                    format_tag = u16::from_le_bytes(body[24..26].try_into()?);
+                }
+                format = Some(WavFormat {
+                    format_tag,
+                    channels: u16::from_le_bytes(body[2..4].try_into()?),
+                    sample_rate: u32::from_le_bytes(body[4..8].try_into()?),
+                    bits_per_sample: u16::from_le_bytes(body[14..16].try_into()?),
+                });
+            }
+            b"data" => {
+                let format = format.context("data 块之前缺少 fmt 块")?;
+                let samples = decode_samples(&format, body)?;
+                return Ok((samples, format.sample_rate));
+            }
+            _ => {}
+        }
+        // 块长为奇数时有一个填充字节
+        pos += 8 + len + (len & 1);
+    }
+    anyhow::bail!("缺少 data 块")
+}
+
+fn decode_samples(format: &WavFormat, data: &[u8]) -> Result<Vec<f32>> {
+    if format.channels == 0 || format.sample_rate == 0 {
+        anyhow::bail!("声道数或采样率为 0");
+    }
+    let width = format.bits_per_sample as usize / 8;
+    let decode: fn(&[u8]) -> f32 = match (format.format_tag, format.bits_per_sample) {
+        (1, 8) => |b| (b[0] as f32 - 128.0) / 128.0,
+        (1, 16) => |b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+        (1, 24) => |b| i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2_147_483_648.0,
+        (1, 32) => |b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0,
+        (3, 32) => |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        (tag, bits) => anyhow::bail!("不支持的格式: format = {}，位深 = {}", tag, bits),
+    };
+
+    let channels = format.channels as usize;
+    Ok(data
+        .chunks_exact(width * channels)
+        .map(|frame| frame.chunks_exact(width).map(decode).sum::<f32>() / channels as f32)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_wav_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.wav");
+        let samples = vec![0.0, 0.5, -0.5, 1.0];
+        write_wav(&path, &samples, 16000).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 44 + samples.len() * 4);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..12], b"WAVE");
+        assert_eq!(u32::from_le_bytes(bytes[24..28].try_into().unwrap()), 16000);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(f32::from_le_bytes(bytes[48..52].try_into().unwrap()), 0.5);
+    }
+
+    #[test]
+    fn test_read_wav_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.wav");
+        let samples = vec![0.0, 0.25, -0.5, 1.0];
+        write_wav(&path, &samples, 22050).unwrap();
+
+        let (read, rate) = read_wav(&path).unwrap();
+        assert_eq!(rate, 22050);
+        assert_eq!(read, samples);
+    }
+
+    #[test]
+    fn test_read_wav_pcm16_stereo_downmix() {
+        let mut bytes = Vec::new();
+        let data: [i16; 4] = [16384, 0, -32768, -32768];
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + 8u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16000u32.to_le_bytes());
+        bytes.extend_from_slice(&64000u32.to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&8u32.to_le_bytes());
+        for sample in data {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        let (samples, rate) = parse_wav(&bytes).unwrap();
+        assert_eq!(rate, 16000);
+        assert_eq!(samples, vec![0.25, -1.0]);
+    }
+
+    #[test]
+    fn test_read_wav_rejects_garbage() {
+        assert!(parse_wav(b"not a wav file").is_err());
+    }
+}